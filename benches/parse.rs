@@ -0,0 +1,77 @@
+//! Compares parsing a large synthetic program directly into the boxed AST,
+//! into the arena-backed one, and into the arena then converting it to
+//! the boxed AST. Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::io;
+use std::time::{Duration, Instant};
+
+use monkeylang::lexer::{Lexer, Token};
+use monkeylang::parser::{Parser, AST};
+
+const FUNCTIONS: usize = 5_000;
+const RUNS: u32 = 20;
+
+fn main() {
+    let src = synthetic_program();
+    let tokens: Vec<Token> = Lexer::new(io::Cursor::new(&src))
+        .collect::<Result<_, _>>()
+        .expect("source should be lexed");
+    println!("{} bytes, {} tokens", src.len(), tokens.len());
+
+    let boxed = measure(|| {
+        black_box(Parser::new(tokens.iter()).parse().expect("source should be parsed"));
+    });
+    let arena = measure(|| {
+        black_box(Parser::new(tokens.iter()).parse_arena().expect("source should be parsed"));
+    });
+    let converted = measure(|| {
+        let ast = Parser::new(tokens.iter()).parse_arena().expect("source should be parsed");
+        black_box(AST::from(ast));
+    });
+    println!("boxed AST:           {:?} per run", boxed);
+    println!("arena AST:           {:?} per run", arena);
+    println!("arena, then boxed:   {:?} per run", converted);
+    println!("arena speedup:       {:.2}x", boxed.as_secs_f64() / arena.as_secs_f64());
+}
+
+/// Returns the mean duration of `RUNS` runs, after a warm-up run.
+fn measure(mut run: impl FnMut()) -> Duration {
+    run();
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        run();
+    }
+    start.elapsed() / RUNS
+}
+
+/// Generates functions with arithmetic, conditionals, calls and collections,
+/// so that most nodes are the small expressions that used to be boxed one by one.
+fn synthetic_program() -> String {
+    let mut src = String::new();
+    for i in 0..FUNCTIONS {
+        let name = identifier(i);
+        src.push_str(&format!(
+            "let {name} = fn(a, b) {{
+    let c = (a + b * {i}) / (a - -b) * !(a == b);
+    if (c > {i} && a < b || !c) {{ return [a, b, c][1] + f(c, a * 2); }} else {{ c = c + {i}; }}
+    let h = {{\"k\": a - b, \"v\": [c, c * c, c / 2]}};
+    h[\"k\"] + (a |> g(b) |> g({i}));
+}};
+"
+        ));
+    }
+    src
+}
+
+/// Returns a unique identifier for `n`, since identifiers cannot contain digits.
+fn identifier(mut n: usize) -> String {
+    let mut name = String::from("f_");
+    loop {
+        name.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+        if n == 0 {
+            return name;
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::lexer::LexemeKind;
+
+/// The kind of a node, each grouping the lexemes of a syntactic construct.
+/// Trivia between two tokens belongs to the innermost node containing both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    /// The whole source.
+    Root,
+
+    // Statements, each including its doc comment and semicolon if any.
+    LetStatement,
+    /// `fn name(parameters) { body }`.
+    FunctionDeclaration,
+    ReturnStatement,
+    WhileStatement,
+    ForStatement,
+    BreakStatement,
+    ContinueStatement,
+    ImportStatement,
+    /// `export` followed by the exported statement.
+    ExportStatement,
+    ExpressionStatement,
+    /// Statements enclosed in braces, the braces included.
+    Block,
+
+    // Expressions.
+    /// A boolean, integer or string.
+    Literal,
+    /// An identifier referring to a binding.
+    Name,
+    /// An expression enclosed in parentheses, the parentheses included.
+    ParenExpr,
+    UnaryExpr,
+    BinaryExpr,
+    LogicalExpr,
+    AssignExpr,
+    ArrayExpr,
+    HashExpr,
+    /// A `key: value` pair of a hash literal.
+    HashEntry,
+    IndexExpr,
+    IfExpr,
+    MatchExpr,
+    /// `pattern if guard => body` in a match expression.
+    MatchArm,
+    FunctionExpr,
+    /// `|parameters| body`, or `|| body`.
+    LambdaExpr,
+    MacroExpr,
+    CallExpr,
+    /// `x |> f`, which the AST lowers to a call.
+    PipeExpr,
+    /// The parenthesized arguments of a call.
+    ArgumentList,
+    /// `name: value` in an argument list.
+    NamedArgument,
+    /// The parameters of a function, lambda or macro, with their delimiters.
+    ParameterList,
+    /// A parameter, along with its annotation and default value.
+    Parameter,
+    /// `...name` in a parameter list.
+    RestParameter,
+
+    // Patterns.
+    WildcardPattern,
+    IdentifierPattern,
+    LiteralPattern,
+    ArrayPattern,
+    /// `...name` in an array pattern.
+    RestPattern,
+    HashPattern,
+    /// A `key: pattern` pair of a hash pattern, or the `name` shorthand.
+    HashPatternEntry,
+
+    // Types.
+    NamedType,
+    ArrayType,
+    HashType,
+    FunctionType,
+}
+
+impl NodeKind {
+    pub fn is_statement(self) -> bool {
+        matches!(
+            self,
+            NodeKind::LetStatement
+                | NodeKind::FunctionDeclaration
+                | NodeKind::ReturnStatement
+                | NodeKind::WhileStatement
+                | NodeKind::ForStatement
+                | NodeKind::BreakStatement
+                | NodeKind::ContinueStatement
+                | NodeKind::ImportStatement
+                | NodeKind::ExportStatement
+                | NodeKind::ExpressionStatement
+        )
+    }
+}
+
+/// An immutable lexeme, not knowing its position in the source.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GreenToken {
+    kind: LexemeKind,
+    text: String,
+}
+
+impl GreenToken {
+    pub fn new(kind: LexemeKind, text: String) -> Self {
+        Self { kind, text }
+    }
+
+    pub fn kind(&self) -> &LexemeKind {
+        &self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// An immutable node, not knowing its position in the source
+/// and thus shareable between trees.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GreenNode {
+    kind: NodeKind,
+    /// Length of the node's text, in bytes.
+    len: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: NodeKind, children: Vec<GreenElement>) -> Self {
+        let len = children.iter().map(GreenElement::len).sum();
+        Self { kind, len, children }
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+
+    /// Returns a copy of the node in which each token is replaced by the
+    /// result of `f`, if any. Subtrees with no replaced tokens are shared.
+    pub fn replace_tokens(self: &Rc<Self>, f: &mut impl FnMut(&GreenToken) -> Option<GreenToken>) -> Rc<Self> {
+        let mut changed = false;
+        let children = self
+            .children
+            .iter()
+            .map(|child| {
+                let new = match child {
+                    GreenElement::Node(node) => GreenElement::Node(node.replace_tokens(f)),
+                    GreenElement::Token(tok) => match f(tok) {
+                        Some(new) => GreenElement::Token(Rc::new(new)),
+                        None => GreenElement::Token(Rc::clone(tok)),
+                    },
+                };
+                changed |= !new.ptr_eq(child);
+                new
+            })
+            .collect();
+        if changed {
+            Rc::new(Self::new(self.kind, children))
+        } else {
+            Rc::clone(self)
+        }
+    }
+}
+
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => write!(f, "{}", node)?,
+                GreenElement::Token(tok) => write!(f, "{}", tok.text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.len,
+            GreenElement::Token(tok) => tok.text.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn ptr_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (GreenElement::Node(a), GreenElement::Node(b)) => Rc::ptr_eq(a, b),
+            (GreenElement::Token(a), GreenElement::Token(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! A lossless concrete syntax tree, keeping every character of the source.
+//!
+//! The tree is split in two layers: green nodes are immutable and position
+//! independent, so editing a tree only rebuilds the path to the change;
+//! syntax nodes wrap them with their parent and position in the source.
+//!
+//! The tree has a node for every statement, expression, pattern, parameter
+//! and type, as recognized by the parser. The AST of a possibly edited tree
+//! is derived by parsing the tokens in it.
+
+mod green;
+pub use green::*;
+
+mod red;
+pub use red::*;
+
+use std::cmp::Reverse;
+use std::io::Read;
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::error::Error;
+use crate::lexer::{Lexeme, LexemeKind, Lexer, Position, Span, Token};
+use crate::parser::{self, Parser, AST};
+
+/// Builds the syntax tree of the source, which must parse.
+pub fn parse<R: Read>(input: R) -> Result<SyntaxNode, Error> {
+    let lexemes = Lexer::new(input).lexemes().collect::<Result<Vec<_>, _>>()?;
+    let (tokens, spans): (Vec<Token>, Vec<Span>) = lexemes
+        .iter()
+        .filter_map(|lexeme| match &lexeme.kind {
+            LexemeKind::Token(tok) => Some((tok.clone(), lexeme.span)),
+            _ => None,
+        })
+        .unzip();
+    let mut parser = Parser::new(tokens.iter()).with_spans(spans).with_syntax();
+    parser.parse_arena()?;
+    Ok(SyntaxNode::new_root(build(lexemes, parser.take_syntax())))
+}
+
+/// Builds the green tree of the lexemes, given the kind and token range of
+/// each node in post-order, as recorded by the parser.
+fn build(lexemes: Vec<Lexeme>, mut nodes: Vec<(NodeKind, Range<usize>)>) -> Rc<GreenNode> {
+    // Nodes are opened from the outermost. Of the nodes spanning the same
+    // tokens, the outer ones were recorded last.
+    nodes.reverse();
+    nodes.sort_by_key(|(_, range)| (range.start, Reverse(range.end)));
+    let mut nodes = nodes.into_iter().peekable();
+    let mut builder = Builder::default();
+    let mut index = 0;
+    for lexeme in lexemes {
+        if !matches!(lexeme.kind, LexemeKind::Token(_)) {
+            builder.push(lexeme);
+            continue;
+        }
+        while let Some((kind, range)) = nodes.next_if(|(_, range)| range.start == index) {
+            builder.open(kind, range.end);
+        }
+        builder.push(lexeme);
+        index += 1;
+        // Trivia after the last token of a node belongs to its parent.
+        while builder.end() == Some(index) {
+            builder.close();
+        }
+    }
+    builder.finish()
+}
+
+impl SyntaxNode {
+    /// Parses the tokens in the node into an AST. The spans in errors
+    /// are relative to the start of the node.
+    pub fn to_ast(&self) -> Result<AST, parser::Error> {
+        let mut tokens = Vec::new();
+        let mut spans = Vec::new();
+        let mut pos = Position::default();
+        for tok in self.tokens() {
+            let start = pos;
+            tok.text().chars().for_each(|ch| pos.advance(ch));
+            if let LexemeKind::Token(kind) = tok.kind() {
+                tokens.push(kind.clone());
+                spans.push(Span { start, end: pos });
+            }
+        }
+        Parser::new(tokens.iter()).with_spans(spans).parse()
+    }
+
+    /// Returns a new tree, rooted at a copy of this node, in which every
+    /// identifier named `old` is named `new`. Bindings are not resolved,
+    /// so all the identifiers with that name are renamed.
+    pub fn rename(&self, old: &str, new: &str) -> SyntaxNode {
+        let green = self.green().replace_tokens(&mut |tok| match tok.kind() {
+            LexemeKind::Token(Token::Identifier(name)) if name == old => Some(GreenToken::new(
+                LexemeKind::Token(Token::Identifier(new.to_string())),
+                new.to_string(),
+            )),
+            _ => None,
+        });
+        SyntaxNode::new_root(green)
+    }
+}
+
+/// Builds green nodes from lexemes, keeping track of the open nodes.
+struct Builder {
+    /// The nodes still open, each with the index of the token after its last
+    /// one, except for the root, and the children collected so far.
+    stack: Vec<(NodeKind, Option<usize>, Vec<GreenElement>)>,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            stack: vec![(NodeKind::Root, None, Vec::new())],
+        }
+    }
+}
+
+impl Builder {
+    fn push(&mut self, lexeme: Lexeme) {
+        let tok = GreenElement::Token(Rc::new(GreenToken::new(lexeme.kind, lexeme.text)));
+        self.stack.last_mut().unwrap().2.push(tok);
+    }
+
+    /// Closes every node left open, and returns the root.
+    fn finish(mut self) -> Rc<GreenNode> {
+        while self.stack.len() > 1 {
+            self.close();
+        }
+        // The stack always holds the root.
+        let (kind, _, children) = self.stack.pop().unwrap();
+        Rc::new(GreenNode::new(kind, children))
+    }
+
+    /// Returns where the innermost open node ends.
+    fn end(&self) -> Option<usize> {
+        self.stack.last().and_then(|(_, end, _)| *end)
+    }
+
+    fn open(&mut self, kind: NodeKind, end: usize) {
+        self.stack.push((kind, Some(end), Vec::new()));
+    }
+
+    fn close(&mut self) {
+        // The root is only closed by finish.
+        if self.stack.len() > 1 {
+            let (kind, _, children) = self.stack.pop().unwrap();
+            let node = GreenElement::Node(Rc::new(GreenNode::new(kind, children)));
+            self.stack.last_mut().unwrap().2.push(node);
+        }
+    }
+}
@@ -2,6 +2,8 @@ use std::fmt;
 use std::io;
 
 use crate::lexer;
+use crate::macros;
+use crate::modules;
 use crate::parser;
 
 #[derive(thiserror::Error, fmt::Debug)]
@@ -14,4 +16,10 @@ pub enum Error {
 
     #[error("I/O error")]
     Parsing(#[from] parser::Error),
+
+    #[error("macro expansion error")]
+    Macro(#[from] macros::Error),
+
+    #[error("module loading error")]
+    Module(#[from] modules::Error),
 }
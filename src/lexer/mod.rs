@@ -1,97 +1,206 @@
 mod error;
 pub use error::*;
 
+mod span;
+pub use span::*;
+
 mod token;
 pub use token::*;
 
-use std::io::{self, Read};
+use std::io::{self, BufReader, Read};
 use std::iter;
 use std::str::FromStr;
 
 pub struct Lexer<R: io::Read> {
-    input: iter::Peekable<io::Bytes<R>>,
+    /// The input is buffered, as reading it byte by byte would be slow otherwise.
+    input: iter::Peekable<io::Bytes<BufReader<R>>>,
     token_buf: String,
+    /// Source text of the lexeme being read.
+    text_buf: String,
+    /// Position of the next character.
+    pos: Position,
+}
+
+/// A token or a piece of trivia, i.e. source that has no meaning for the parser.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LexemeKind {
+    Token(Token),
+    Whitespace,
+    /// A comment, from `//` to the end of the line. Doc comments,
+    /// which start with exactly three slashes, are tokens instead.
+    Comment,
+}
+
+/// A lexeme along with its exact source text and location.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lexeme {
+    pub kind: LexemeKind,
+    pub text: String,
+    pub span: Span,
 }
 
 impl<R: Read + Default> Default for Lexer<R> {
     fn default() -> Self {
-        Self {
-            input: R::default().bytes().peekable(),
-            token_buf: String::with_capacity(32),
-        }
+        Self::new(R::default())
     }
 }
 
 impl<R: Read> Lexer<R> {
     pub fn new(input: R) -> Self {
         Self {
-            input: input.bytes().peekable(),
+            input: BufReader::new(input).bytes().peekable(),
             token_buf: String::with_capacity(32),
+            text_buf: String::with_capacity(32),
+            pos: Position::default(),
         }
     }
 
     /// Resets the Lexer with a new source of data.
     pub fn reset(&mut self, input: R) {
-        self.input = input.bytes().peekable();
+        self.input = BufReader::new(input).bytes().peekable();
+        self.pos = Position::default();
+    }
+
+    /// Returns an iterator over the lexemes, trivia included.
+    pub fn lexemes(self) -> Lexemes<R> {
+        Lexemes(self)
     }
 
     pub fn next_token(&mut self) -> Result<Token, Error> {
-        let ch = self.next_nonwhitespace_char()?;
-        if Token::may_be_two_chars(ch) {
-            let next = self.peek_char()?;
-            if let Some(tok) = Token::from_two_chars(&[ch, next]) {
-                let _ = self.next_char();
+        loop {
+            if let LexemeKind::Token(tok) = self.next_lexeme()?.kind {
                 return Ok(tok);
             }
         }
+    }
+
+    pub fn next_lexeme(&mut self) -> Result<Lexeme, Error> {
+        self.text_buf.clear();
+        let start = self.pos;
+        let ch = self.next_char()?;
+        let kind = if ch.is_ascii_whitespace() {
+            self.skip_while(|ch| ch.is_ascii_whitespace())?;
+            LexemeKind::Whitespace
+        } else if ch == '/' && self.peek_char()? == Some('/') {
+            self.skip_while(|ch| ch != '\n')?;
+            match doc_comment(&self.text_buf) {
+                Some(doc) => LexemeKind::Token(Token::DocComment(doc)),
+                None => LexemeKind::Comment,
+            }
+        } else {
+            LexemeKind::Token(self.read_token(ch)?)
+        };
+        Ok(Lexeme {
+            kind,
+            text: self.text_buf.clone(),
+            span: Span { start, end: self.pos },
+        })
+    }
+
+    /// Reads the token starting with `ch`, which has already been consumed.
+    fn read_token(&mut self, ch: char) -> Result<Token, Error> {
+        if Token::may_be_two_chars(ch) {
+            if let Some(next) = self.peek_char()? {
+                if let Some(tok) = Token::from_two_chars(&[ch, next]) {
+                    let _ = self.next_char();
+                    return Ok(tok);
+                }
+            }
+        }
         if let Some(tok) = Token::from_char(ch) {
             return Ok(tok);
         }
+        if ch == '"' {
+            return self.read_string();
+        }
         self.token_buf.clear();
         self.token_buf.push(ch);
         if is_identifier_char(ch) {
             self.fill_buffer_until(is_identifier_char)?;
         } else if ch.is_ascii_digit() {
             self.fill_buffer_until(|ch| ch.is_ascii_digit())?;
+        } else if ch == '.' {
+            // Dots only make up `...`: any other run of them is invalid.
+            self.fill_buffer_until(|ch| ch == '.')?;
+            if self.token_buf != "..." {
+                return Err(Error::Token(self.token_buf.clone()));
+            }
         }
         Token::from_str(&self.token_buf).map_err(|_| Error::Token(self.token_buf.clone()))
     }
 
-    /// Returns the next character.
+    /// Reads a string literal up to the closing double quote,
+    /// the opening one having already been consumed.
+    fn read_string(&mut self) -> Result<Token, Error> {
+        self.token_buf.clear();
+        loop {
+            match self.next_char() {
+                Ok('"') => return Ok(Token::String(self.token_buf.clone())),
+                Ok(ch) => self.token_buf.push(ch),
+                Err(Error::IO(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Err(Error::Token(format!("\"{}", self.token_buf)))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Returns the next character, decoding it from UTF-8.
     fn next_char(&mut self) -> Result<char, Error> {
+        let first = self.next_byte()?;
+        let len = match first {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return Err(Error::Token(format!("{:x}", first))),
+        };
+        let mut bytes = [first, 0, 0, 0];
+        for byte in bytes.iter_mut().take(len).skip(1) {
+            *byte = self.next_byte()?;
+        }
+        let ch = std::str::from_utf8(&bytes[..len])
+            .ok()
+            .and_then(|s| s.chars().next())
+            .ok_or_else(|| Error::Token(format!("{:x}", first)))?;
+
+        self.pos.advance(ch);
+        self.text_buf.push(ch);
+        Ok(ch)
+    }
+
+    fn next_byte(&mut self) -> Result<u8, Error> {
         let byte = self
             .input
             .next()
             .unwrap_or(Err(io::Error::from(io::ErrorKind::UnexpectedEof)))?;
-        char::from_u32(byte as u32).ok_or_else(|| Error::Token(format!("{:x}", byte)))
+        Ok(byte)
     }
 
-    /// Peeks the next character.
-    fn peek_char(&mut self) -> Result<char, Error> {
-        let byte = self
-            .input
-            .peek()
-            .map(|result| match result {
-                Ok(byte) => Ok(*byte),
-                Err(e) => Err(io::Error::from(e.kind())),
-            })
-            .unwrap_or(Err(io::Error::from(io::ErrorKind::UnexpectedEof)))
-            .map_err(Error::from)?;
-        char::from_u32(byte as u32).ok_or_else(|| Error::Token(format!("{:x}", byte)))
+    /// Peeks the next character, or returns None at the end of the source.
+    /// Only ASCII characters are meaningful when peeking, so any other
+    /// character is returned as U+FFFD.
+    fn peek_char(&mut self) -> Result<Option<char>, Error> {
+        match self.input.peek() {
+            None => Ok(None),
+            Some(Ok(byte)) if byte.is_ascii() => Ok(Some(*byte as char)),
+            Some(Ok(_)) => Ok(Some(char::REPLACEMENT_CHARACTER)),
+            Some(Err(e)) => Err(Error::from(io::Error::from(e.kind()))),
+        }
     }
 
-    fn next_nonwhitespace_char(&mut self) -> Result<char, Error> {
-        loop {
-            let ch = self.next_char()?;
-            if !ch.is_ascii_whitespace() {
-                return Ok(ch);
+    fn skip_while(&mut self, cond: impl Fn(char) -> bool) -> Result<(), Error> {
+        while let Some(ch) = self.peek_char()? {
+            if !cond(ch) {
+                break;
             }
+            self.next_char()?;
         }
+        Ok(())
     }
 
     fn fill_buffer_until(&mut self, cond: impl Fn(char) -> bool) -> Result<(), Error> {
-        loop {
-            let ch = self.peek_char()?;
+        while let Some(ch) = self.peek_char()? {
             if !cond(ch) {
                 break;
             }
@@ -106,16 +215,32 @@ impl<R: io::Read> iter::Iterator for Lexer<R> {
     type Item = Result<Token, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.next_token() {
-            Ok(tok) => Some(Ok(tok)),
-            Err(err) => {
-                if let Error::IO(ref e) = err {
-                    if e.kind() == io::ErrorKind::UnexpectedEof {
-                        return None;
-                    }
+        end_at_eof(self.next_token())
+    }
+}
+
+/// An iterator over the tokens and the trivia of the source.
+pub struct Lexemes<R: Read>(Lexer<R>);
+
+impl<R: Read> iter::Iterator for Lexemes<R> {
+    type Item = Result<Lexeme, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        end_at_eof(self.0.next_lexeme())
+    }
+}
+
+/// Turns the error signaling the end of the source into the end of an iteration.
+fn end_at_eof<T>(result: Result<T, Error>) -> Option<Result<T, Error>> {
+    match result {
+        Ok(item) => Some(Ok(item)),
+        Err(err) => {
+            if let Error::IO(ref e) = err {
+                if e.kind() == io::ErrorKind::UnexpectedEof {
+                    return None;
                 }
-                Some(Err(err))
             }
+            Some(Err(err))
         }
     }
 }
@@ -123,3 +248,14 @@ impl<R: io::Read> iter::Iterator for Lexer<R> {
 fn is_identifier_char(ch: char) -> bool {
     ch.is_ascii_alphabetic() || ch == '_'
 }
+
+/// Returns the text of `comment` if it is a doc comment, without
+/// the slashes and the space that usually follows them.
+fn doc_comment(comment: &str) -> Option<String> {
+    let text = comment.strip_prefix("///")?;
+    if text.starts_with('/') {
+        return None;
+    }
+    let text = text.strip_suffix('\r').unwrap_or(text);
+    Some(text.strip_prefix(' ').unwrap_or(text).to_string())
+}
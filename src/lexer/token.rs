@@ -4,39 +4,70 @@ use std::str::FromStr;
 use super::Error;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Token {
     // Single-char long.
     Assign,
     Asterisk,
     Bang,
+    /// A `|`, which encloses the parameters of a short lambda like `|x| x * 2`.
+    Bar,
+    Colon,
     Comma,
     GreaterThan,
     LeftBrace,
+    LeftBracket,
     LeftParen,
     LessThan,
     Minus,
     Plus,
     RightBrace,
+    RightBracket,
     RightParen,
     Semicolon,
     Slash,
 
     // Two-char long.
+    And,
+    Arrow,
+    AsteriskAssign,
     Equal,
+    FatArrow,
+    MinusAssign,
     NotEqual,
+    Or,
+    Pipe,
+    PlusAssign,
+    SlashAssign,
+
+    // Three-char long.
+    Ellipsis,
 
     // Multichar reserved words.
+    As,
+    Break,
+    Continue,
     Else,
+    Export,
     False,
+    For,
     Function,
     If,
+    Import,
+    In,
     Let,
+    Macro,
+    Match,
     Return,
     True,
+    While,
 
     // User-provided values.
+    /// A line of documentation, written after `///`.
+    DocComment(String),
     Identifier(String),
     Integer(i64),
+    String(String),
 }
 
 impl Token {
@@ -45,14 +76,18 @@ impl Token {
             '=' => Some(Self::Assign),
             '*' => Some(Self::Asterisk),
             '!' => Some(Self::Bang),
+            '|' => Some(Self::Bar),
+            ':' => Some(Self::Colon),
             ',' => Some(Self::Comma),
             '>' => Some(Self::GreaterThan),
             '{' => Some(Self::LeftBrace),
+            '[' => Some(Self::LeftBracket),
             '(' => Some(Self::LeftParen),
             '<' => Some(Self::LessThan),
             '-' => Some(Self::Minus),
             '+' => Some(Self::Plus),
             '}' => Some(Self::RightBrace),
+            ']' => Some(Self::RightBracket),
             ')' => Some(Self::RightParen),
             ';' => Some(Self::Semicolon),
             '/' => Some(Self::Slash),
@@ -61,18 +96,24 @@ impl Token {
     }
 
     pub fn from_two_chars(chs: &[char; 2]) -> Option<Self> {
-        if chs[1] != '=' {
-            return None;
-        }
-        match chs[0] {
-            '!' => Some(Self::NotEqual),
-            '=' => Some(Self::Equal),
+        match chs {
+            ['!', '='] => Some(Self::NotEqual),
+            ['=', '='] => Some(Self::Equal),
+            ['=', '>'] => Some(Self::FatArrow),
+            ['*', '='] => Some(Self::AsteriskAssign),
+            ['-', '='] => Some(Self::MinusAssign),
+            ['-', '>'] => Some(Self::Arrow),
+            ['+', '='] => Some(Self::PlusAssign),
+            ['/', '='] => Some(Self::SlashAssign),
+            ['&', '&'] => Some(Self::And),
+            ['|', '|'] => Some(Self::Or),
+            ['|', '>'] => Some(Self::Pipe),
             _ => None,
         }
     }
 
     pub fn may_be_two_chars(ch: char) -> bool {
-        ch == '!' || ch == '='
+        matches!(ch, '!' | '=' | '*' | '-' | '+' | '/' | '&' | '|')
     }
 }
 
@@ -96,13 +137,24 @@ impl FromStr for Token {
             }
         }
         match s {
+            "..." => Ok(Self::Ellipsis),
+            "as" => Ok(Self::As),
+            "break" => Ok(Self::Break),
+            "continue" => Ok(Self::Continue),
             "else" => Ok(Self::Else),
+            "export" => Ok(Self::Export),
             "false" => Ok(Self::False),
+            "for" => Ok(Self::For),
             "fn" => Ok(Self::Function),
             "if" => Ok(Self::If),
+            "import" => Ok(Self::Import),
+            "in" => Ok(Self::In),
             "let" => Ok(Self::Let),
+            "macro" => Ok(Self::Macro),
+            "match" => Ok(Self::Match),
             "return" => Ok(Self::Return),
             "true" => Ok(Self::True),
+            "while" => Ok(Self::While),
             _ => {
                 if first.is_ascii_digit() {
                     return i64::from_str(s)
@@ -118,31 +170,57 @@ impl FromStr for Token {
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Token::And => write!(f, "&&"),
+            Token::Arrow => write!(f, "->"),
+            Token::As => write!(f, "as"),
             Token::Assign => write!(f, "="),
             Token::Asterisk => write!(f, "*"),
+            Token::AsteriskAssign => write!(f, "*="),
             Token::Bang => write!(f, "!"),
+            Token::Bar => write!(f, "|"),
+            Token::Break => write!(f, "break"),
+            Token::Colon => write!(f, ":"),
             Token::Comma => write!(f, ","),
+            Token::Continue => write!(f, "continue"),
+            Token::DocComment(s) => write!(f, "/// {}", s),
+            Token::Ellipsis => write!(f, "..."),
             Token::Else => write!(f, "else"),
             Token::Equal => write!(f, "=="),
+            Token::Export => write!(f, "export"),
             Token::False => write!(f, "false"),
+            Token::FatArrow => write!(f, "=>"),
+            Token::For => write!(f, "for"),
             Token::Function => write!(f, "fn"),
             Token::GreaterThan => write!(f, ">"),
             Token::Identifier(s) => write!(f, "{}", s),
             Token::If => write!(f, "if"),
+            Token::Import => write!(f, "import"),
+            Token::In => write!(f, "in"),
             Token::Integer(int) => write!(f, "{}", int),
             Token::LeftBrace => write!(f, "{{"),
+            Token::LeftBracket => write!(f, "["),
             Token::LeftParen => write!(f, "("),
             Token::LessThan => write!(f, "<"),
             Token::Let => write!(f, "let"),
+            Token::Macro => write!(f, "macro"),
+            Token::Match => write!(f, "match"),
             Token::Minus => write!(f, "-"),
+            Token::MinusAssign => write!(f, "-="),
             Token::NotEqual => write!(f, "!="),
+            Token::Or => write!(f, "||"),
+            Token::Pipe => write!(f, "|>"),
             Token::Plus => write!(f, "+"),
+            Token::PlusAssign => write!(f, "+="),
             Token::Return => write!(f, "return"),
             Token::RightBrace => write!(f, "}}"),
+            Token::RightBracket => write!(f, "]"),
             Token::RightParen => write!(f, ")"),
             Token::Semicolon => write!(f, ";"),
             Token::Slash => write!(f, "/"),
+            Token::SlashAssign => write!(f, "/="),
+            Token::String(s) => write!(f, "\"{}\"", s),
             Token::True => write!(f, "true"),
+            Token::While => write!(f, "while"),
         }
     }
 }
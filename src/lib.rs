@@ -1,4 +1,8 @@
 pub mod console;
+pub mod cst;
 pub mod error;
 pub mod lexer;
+pub mod macros;
+pub mod modules;
 pub mod parser;
+pub mod typecheck;
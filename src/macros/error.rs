@@ -0,0 +1,44 @@
+use std::fmt;
+
+use crate::parser::{Expression, Statement};
+
+#[derive(thiserror::Error, fmt::Debug)]
+pub enum Error {
+    #[error("macro {name} expects {expected} arguments, got {got}")]
+    ArgumentCount { name: String, expected: usize, got: usize },
+
+    #[error("macro {0} takes no named arguments")]
+    NamedArguments(String),
+
+    #[error("macro {0} does not return a quote")]
+    NotQuoted(String),
+
+    /// `unquote` is not given exactly one argument,
+    /// or its argument evaluates to a value that is not code.
+    #[error("cannot unquote {0:?}")]
+    Unquote(Box<Expression>),
+
+    #[error("{0} is not defined in the macro")]
+    Undefined(String),
+
+    /// The expression is outside the part of the language macros can run,
+    /// or its operands have the wrong types.
+    #[error("cannot evaluate {0:?} while expanding a macro")]
+    Evaluation(Box<Expression>),
+
+    #[error("cannot run {0:?} while expanding a macro")]
+    Statement(Box<Statement>),
+
+    /// The code returned by macro `name` keeps calling macros
+    /// after `limit` nested expansions.
+    #[error("expanding macro {name} nests more than {limit} expansions")]
+    TooDeep { name: String, limit: usize },
+
+    /// The body of a macro evaluates more than `limit` expressions
+    /// for one call, likely because it loops forever.
+    #[error("running a macro body takes more than {limit} steps")]
+    TooManySteps { limit: usize },
+
+    #[error("macro literals can only be bound to a name by a let statement")]
+    UnboundLiteral,
+}
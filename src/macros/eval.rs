@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use super::{Error, QUOTE, STEP_LIMIT, UNQUOTE};
+use crate::lexer::Token;
+use crate::parser::visit::{self, Folder};
+use crate::parser::{Expression, Pattern, Statement};
+
+/// A value computed by the body of a macro.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Value {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    String(String),
+    Array(Vec<Value>),
+    /// Code returned by `quote`, or passed as an argument to the macro.
+    Quote(Expression),
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Null | Value::Boolean(false))
+    }
+
+    /// Turns the value back into code, so that it can be spliced into a quote.
+    fn into_expression(self) -> Option<Expression> {
+        Some(match self {
+            Value::Null => return None,
+            Value::Boolean(b) => Expression::Boolean(b),
+            Value::Integer(int) => Expression::Integer(int),
+            Value::String(s) => Expression::String(s),
+            Value::Array(elems) => {
+                Expression::Array(elems.into_iter().map(Value::into_expression).collect::<Option<_>>()?)
+            }
+            Value::Quote(exp) => exp,
+        })
+    }
+}
+
+/// How evaluating a statement ends.
+enum Flow {
+    Next(Value),
+    Return(Value),
+    Break,
+    Continue,
+}
+
+/// Evaluates the body of a macro. Only the part of the language that
+/// computes code is supported: literals, arrays, operators, bindings,
+/// conditionals and loops, along with `quote` and `unquote`.
+pub(super) struct Evaluator {
+    /// The bindings in each nested scope.
+    scopes: Vec<HashMap<String, Value>>,
+    /// Number of expressions evaluated so far, bounded by [`STEP_LIMIT`].
+    steps: usize,
+}
+
+impl Evaluator {
+    pub fn new(bindings: impl IntoIterator<Item = (String, Value)>) -> Self {
+        Self {
+            scopes: vec![bindings.into_iter().collect()],
+            steps: 0,
+        }
+    }
+
+    /// Returns the value of the body, that is, the value returned
+    /// or else the value of its last statement.
+    pub fn eval_body(&mut self, body: &[Statement]) -> Result<Value, Error> {
+        match self.eval_block(body)? {
+            Flow::Next(value) | Flow::Return(value) => Ok(value),
+            // The parser rejects loop control outside of loops.
+            Flow::Break | Flow::Continue => Ok(Value::Null),
+        }
+    }
+
+    fn eval_block(&mut self, statements: &[Statement]) -> Result<Flow, Error> {
+        self.scopes.push(HashMap::new());
+        let flow = self.eval_statements(statements);
+        self.scopes.pop();
+        flow
+    }
+
+    fn eval_statements(&mut self, statements: &[Statement]) -> Result<Flow, Error> {
+        let mut last = Value::Null;
+        for st in statements {
+            match self.eval_statement(st)? {
+                Flow::Next(value) => last = value,
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next(last))
+    }
+
+    fn eval_statement(&mut self, st: &Statement) -> Result<Flow, Error> {
+        match st {
+            // A branch may return from the macro or control the enclosing loop.
+            Statement::Expression(Expression::If { cond, conseq, altern }) => {
+                return self.eval_if(cond, conseq, altern.as_deref())
+            }
+            Statement::Expression(exp) => return self.eval(exp).map(Flow::Next),
+            Statement::Let {
+                pattern: Pattern::Identifier(name),
+                expression,
+                ..
+            } => {
+                let value = self.eval(expression)?;
+                self.scopes.last_mut().expect("a scope is always open").insert(name.clone(), value);
+            }
+            Statement::Return(exp) => return self.eval(exp).map(Flow::Return),
+            Statement::While { cond, body } => {
+                while self.eval(cond)?.is_truthy() {
+                    match self.eval_block(body)? {
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Break => break,
+                        Flow::Next(_) | Flow::Continue => {}
+                    }
+                }
+            }
+            Statement::For {
+                variable,
+                iterable,
+                body,
+            } => {
+                let Value::Array(elems) = self.eval(iterable)? else {
+                    return Err(Error::Evaluation(Box::new(iterable.clone())));
+                };
+                for elem in elems {
+                    self.scopes.push(HashMap::from([(variable.clone(), elem)]));
+                    let flow = self.eval_block(body);
+                    self.scopes.pop();
+                    match flow? {
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Break => break,
+                        Flow::Next(_) | Flow::Continue => {}
+                    }
+                }
+            }
+            Statement::Break => return Ok(Flow::Break),
+            Statement::Continue => return Ok(Flow::Continue),
+            Statement::Let { .. } | Statement::Import { .. } | Statement::Export(_) => {
+                return Err(Error::Statement(Box::new(st.clone())))
+            }
+        }
+        Ok(Flow::Next(Value::Null))
+    }
+
+    fn eval(&mut self, exp: &Expression) -> Result<Value, Error> {
+        self.steps += 1;
+        if self.steps > STEP_LIMIT {
+            return Err(Error::TooManySteps { limit: STEP_LIMIT });
+        }
+        let invalid = || Error::Evaluation(Box::new(exp.clone()));
+        Ok(match exp {
+            Expression::None => Value::Null,
+            Expression::Boolean(b) => Value::Boolean(*b),
+            Expression::Integer(int) => Value::Integer(*int),
+            Expression::String(s) => Value::String(s.clone()),
+            Expression::Identifier(name) => self.lookup(name).cloned().ok_or_else(|| Error::Undefined(name.clone()))?,
+            Expression::Array(elems) => Value::Array(elems.iter().map(|elem| self.eval(elem)).collect::<Result<_, _>>()?),
+            Expression::Index { left, index } => match (self.eval(left)?, self.eval(index)?) {
+                (Value::Array(elems), Value::Integer(i)) => {
+                    usize::try_from(i).ok().and_then(|i| elems.get(i).cloned()).unwrap_or(Value::Null)
+                }
+                _ => return Err(invalid()),
+            },
+            Expression::Unary { operator, expression } => match (operator, self.eval(expression)?) {
+                (Token::Bang, value) => Value::Boolean(!value.is_truthy()),
+                (Token::Minus, Value::Integer(int)) => Value::Integer(int.checked_neg().ok_or_else(invalid)?),
+                _ => return Err(invalid()),
+            },
+            Expression::Binary { left, operator, right } => {
+                let (left, right) = (self.eval(left)?, self.eval(right)?);
+                binary(operator, left, right).ok_or_else(invalid)?
+            }
+            Expression::Logical { left, operator, right } => {
+                let left = self.eval(left)?;
+                // `&&` stops at a falsy operand and `||` at a truthy one.
+                if left.is_truthy() == (operator == &Token::Or) {
+                    left
+                } else {
+                    self.eval(right)?
+                }
+            }
+            Expression::If { cond, conseq, altern } => match self.eval_if(cond, conseq, altern.as_deref())? {
+                Flow::Next(value) => value,
+                // Only an if statement can leave the code around it.
+                _ => return Err(invalid()),
+            },
+            Expression::Assign {
+                target,
+                operator,
+                value,
+            } => {
+                let Expression::Identifier(name) = target.as_ref() else {
+                    return Err(invalid());
+                };
+                let mut value = self.eval(value)?;
+                if let Some(operator) = operator {
+                    let current = self.lookup(name).cloned().ok_or_else(|| Error::Undefined(name.clone()))?;
+                    value = binary(operator, current, value).ok_or_else(invalid)?;
+                }
+                let binding = self.lookup_mut(name).ok_or_else(|| Error::Undefined(name.clone()))?;
+                *binding = value.clone();
+                value
+            }
+            Expression::Call { function, arguments, .. } if is_named(function, QUOTE) => match arguments.as_slice() {
+                [code] => Value::Quote(Unquoter { evaluator: self }.fold_expression(code.clone())?),
+                _ => return Err(invalid()),
+            },
+            _ => return Err(invalid()),
+        })
+    }
+
+    fn eval_if(&mut self, cond: &Expression, conseq: &[Statement], altern: Option<&[Statement]>) -> Result<Flow, Error> {
+        if self.eval(cond)?.is_truthy() {
+            self.eval_block(conseq)
+        } else if let Some(altern) = altern {
+            self.eval_block(altern)
+        } else {
+            Ok(Flow::Next(Value::Null))
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Value> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn lookup_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name))
+    }
+}
+
+/// Replaces the calls to unquote in quoted code with the code their argument evaluates to.
+struct Unquoter<'a> {
+    evaluator: &'a mut Evaluator,
+}
+
+impl Folder for Unquoter<'_> {
+    type Error = Error;
+
+    fn fold_expression(&mut self, exp: Expression) -> Result<Expression, Error> {
+        match &exp {
+            Expression::Call { function, arguments, .. } if is_named(function, UNQUOTE) => match arguments.as_slice() {
+                [arg] => self.evaluator.eval(arg)?.into_expression().ok_or(Error::Unquote(Box::new(exp))),
+                _ => Err(Error::Unquote(Box::new(exp))),
+            },
+            _ => visit::walk_expression_fold(self, exp),
+        }
+    }
+}
+
+fn is_named(exp: &Expression, name: &str) -> bool {
+    matches!(exp, Expression::Identifier(id) if id == name)
+}
+
+fn binary(operator: &Token, left: Value, right: Value) -> Option<Value> {
+    Some(match (operator, left, right) {
+        (Token::Equal, left, right) => Value::Boolean(left == right),
+        (Token::NotEqual, left, right) => Value::Boolean(left != right),
+        (Token::Plus, Value::String(left), Value::String(right)) => Value::String(left + &right),
+        (Token::Plus, Value::Array(mut left), Value::Array(right)) => {
+            left.extend(right);
+            Value::Array(left)
+        }
+        (operator, Value::Integer(left), Value::Integer(right)) => match operator {
+            Token::Plus => Value::Integer(left.checked_add(right)?),
+            Token::Minus => Value::Integer(left.checked_sub(right)?),
+            Token::Asterisk => Value::Integer(left.checked_mul(right)?),
+            Token::Slash => Value::Integer(left.checked_div(right)?),
+            Token::LessThan => Value::Boolean(left < right),
+            Token::GreaterThan => Value::Boolean(left > right),
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
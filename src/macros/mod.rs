@@ -0,0 +1,192 @@
+mod error;
+pub use error::*;
+
+mod eval;
+use eval::{Evaluator, Value};
+
+use std::collections::HashMap;
+use std::mem;
+use std::rc::Rc;
+
+use crate::parser::visit::{self, Folder};
+use crate::parser::{Expression, Pattern, Statement, AST};
+
+const QUOTE: &str = "quote";
+const UNQUOTE: &str = "unquote";
+
+/// Maximum number of nested expansions, that is, of macro calls found
+/// in the code returned by a macro, then in the code that call returns,
+/// and so on. It stops macros that expand to themselves forever.
+pub const EXPANSION_LIMIT: usize = 64;
+
+/// Maximum number of expressions evaluated while running the body of a
+/// macro for one call. It stops macro bodies that loop forever.
+pub const STEP_LIMIT: usize = 1_000_000;
+
+struct Macro {
+    parameters: Vec<String>,
+    body: Vec<Statement>,
+}
+
+/// Removes the macro definitions from the AST and replaces the calls
+/// to those macros with the code they return.
+///
+/// A macro is defined by a let statement binding a macro literal, exported
+/// or not, and can be called anywhere in the enclosing block, including before
+/// its definition, until a later statement binds its name to something else.
+/// Its arguments are not evaluated but passed as AST. The macro body is run
+/// at expansion time and must return code, built with `quote(...)`, where
+/// `unquote(exp)` splices in the code `exp` evaluates to, e.g. an argument.
+///
+/// The code returned is expanded in turn, until no macro calls are left
+/// or [`EXPANSION_LIMIT`] is reached.
+pub fn expand_macros(ast: &mut AST) -> Result<(), Error> {
+    let statements = mem::take(ast.statements_mut());
+    let mut expander = Expander {
+        scopes: Vec::new(),
+        depth: 0,
+    };
+    *ast.statements_mut() = expander.fold_block(statements)?;
+    Ok(())
+}
+
+/// Replaces macro calls bottom-up, so that the arguments of a call
+/// are already expanded when they are passed to the macro.
+struct Expander {
+    /// The macros defined in each enclosing block, the innermost last.
+    /// `None` marks a name bound to something else, which shadows the
+    /// macros of that name in the enclosing blocks.
+    scopes: Vec<HashMap<String, Option<Rc<Macro>>>>,
+    /// Number of expansions the code being expanded comes from.
+    depth: usize,
+}
+
+impl Folder for Expander {
+    type Error = Error;
+
+    fn fold_block(&mut self, block: Vec<Statement>) -> Result<Vec<Statement>, Error> {
+        // Every macro of the block can be called from its start.
+        let macros = block.iter().filter_map(macro_definition).map(|(name, mac)| (name, Some(mac))).collect();
+        self.scopes.push(macros);
+        let block = self.fold_statements(block);
+        self.scopes.pop();
+        block
+    }
+
+    fn fold_expression(&mut self, exp: Expression) -> Result<Expression, Error> {
+        match visit::walk_expression_fold(self, exp)? {
+            Expression::Macro { .. } => Err(Error::UnboundLiteral),
+            Expression::Call {
+                function,
+                arguments,
+                named,
+            } => match *function {
+                Expression::Identifier(name) if self.lookup(&name).is_some() => self.expand(name, arguments, named),
+                function => Ok(Expression::Call {
+                    function: Box::new(function),
+                    arguments,
+                    named,
+                }),
+            },
+            exp => Ok(exp),
+        }
+    }
+}
+
+impl Expander {
+    fn lookup(&self, name: &str) -> Option<Rc<Macro>> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned().flatten()
+    }
+
+    /// Expands the statements of a block in order, dropping the macro definitions.
+    fn fold_statements(&mut self, block: Vec<Statement>) -> Result<Vec<Statement>, Error> {
+        let mut statements = Vec::new();
+        for st in block {
+            if let Some((name, mac)) = macro_definition(&st) {
+                let scope = self.scopes.last_mut().expect("a scope is open for the block");
+                scope.insert(name, Some(mac));
+                continue;
+            }
+            let st = self.fold_statement(st)?;
+            // Calls after a binding of the same name are no longer macro calls.
+            let scope = self.scopes.last_mut().expect("a scope is open for the block");
+            for name in bound_names(&st) {
+                scope.insert(name, None);
+            }
+            statements.push(st);
+        }
+        Ok(statements)
+    }
+
+    /// Returns the code returned by macro `name`, expanded in turn.
+    fn expand(&mut self, name: String, arguments: Vec<Expression>, named: Vec<(String, Expression)>) -> Result<Expression, Error> {
+        let mac = self.lookup(&name).expect("the macro should be defined");
+        if !named.is_empty() {
+            return Err(Error::NamedArguments(name));
+        }
+        if arguments.len() != mac.parameters.len() {
+            return Err(Error::ArgumentCount {
+                name,
+                expected: mac.parameters.len(),
+                got: arguments.len(),
+            });
+        }
+        if self.depth == EXPANSION_LIMIT {
+            return Err(Error::TooDeep {
+                name,
+                limit: EXPANSION_LIMIT,
+            });
+        }
+        let bindings = mac.parameters.iter().cloned().zip(arguments.into_iter().map(Value::Quote));
+        let Value::Quote(code) = Evaluator::new(bindings).eval_body(&mac.body)? else {
+            return Err(Error::NotQuoted(name));
+        };
+        self.depth += 1;
+        let code = self.fold_expression(code);
+        self.depth -= 1;
+        code
+    }
+}
+
+/// Returns the name and macro defined by the statement,
+/// if it is a let statement binding a macro literal.
+fn macro_definition(st: &Statement) -> Option<(String, Rc<Macro>)> {
+    match st {
+        Statement::Let {
+            pattern: Pattern::Identifier(name),
+            expression: Expression::Macro { parameters, body },
+            ..
+        } => Some((
+            name.clone(),
+            Rc::new(Macro {
+                parameters: parameters.clone(),
+                body: body.clone(),
+            }),
+        )),
+        Statement::Export(st) => macro_definition(st),
+        _ => None,
+    }
+}
+
+/// Returns the names the statement binds in its block.
+fn bound_names(st: &Statement) -> Vec<String> {
+    fn collect(pattern: &Pattern, names: &mut Vec<String>) {
+        match pattern {
+            Pattern::Identifier(name) => names.push(name.clone()),
+            Pattern::Array { elements, rest } => {
+                elements.iter().for_each(|elem| collect(elem, names));
+                names.extend(rest.clone());
+            }
+            Pattern::Hash(pairs) => pairs.iter().for_each(|(_, pattern)| collect(pattern, names)),
+            Pattern::Wildcard | Pattern::Boolean(_) | Pattern::Integer(_) | Pattern::String(_) => {}
+        }
+    }
+    let mut names = Vec::new();
+    match st {
+        Statement::Let { pattern, .. } => collect(pattern, &mut names),
+        Statement::Export(st) => names = bound_names(st),
+        Statement::Import { alias, .. } => names.push(alias.clone()),
+        _ => {}
+    }
+    names
+}
@@ -0,0 +1,39 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::lexer;
+use crate::parser;
+
+#[derive(thiserror::Error, fmt::Debug)]
+pub enum Error {
+    #[error("cannot read module {}", .path.display())]
+    IO { path: PathBuf, source: io::Error },
+
+    #[error("invalid token in module {}", .path.display())]
+    Lexing { path: PathBuf, source: lexer::Error },
+
+    #[error("cannot parse module {}", .path.display())]
+    Parsing { path: PathBuf, source: parser::Error },
+
+    /// The module imports two modules under the same alias.
+    #[error("module {} imports more than one module as {alias}", .path.display())]
+    DuplicateImport { path: PathBuf, alias: String },
+
+    /// The module exports two bindings of the same name.
+    #[error("module {} exports {name} more than once", .path.display())]
+    DuplicateExport { path: PathBuf, name: String },
+
+    /// A module imported by the last one of `chain`, each module of which imports
+    /// the following one, cannot be loaded because of `source`.
+    #[error("cannot load a module imported through {}", display_chain(.chain))]
+    Import { chain: Vec<PathBuf>, source: Box<Error> },
+
+    /// The chain of imports leading back to the first module in it.
+    #[error("import cycle: {}", display_chain(.0))]
+    Cycle(Vec<PathBuf>),
+}
+
+fn display_chain(chain: &[PathBuf]) -> String {
+    chain.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(" -> ")
+}
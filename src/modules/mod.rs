@@ -0,0 +1,149 @@
+mod error;
+pub use error::*;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::lexer::{LexemeKind, Lexer};
+use crate::parser::{Expression, Parser, Pattern, Statement, AST};
+
+/// A loaded source file, along with the modules it imports.
+#[derive(Debug)]
+pub struct Module {
+    path: PathBuf,
+    ast: AST,
+    imports: HashMap<String, Rc<Module>>,
+    exports: HashMap<String, Expression>,
+}
+
+impl Module {
+    /// Returns the canonical path of the source file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn ast(&self) -> &AST {
+        &self.ast
+    }
+
+    /// Returns the imported modules by the alias they are imported as.
+    pub fn imports(&self) -> &HashMap<String, Rc<Module>> {
+        &self.imports
+    }
+
+    /// Returns the namespace of the module, i.e. its exported bindings.
+    pub fn exports(&self) -> &HashMap<String, Expression> {
+        &self.exports
+    }
+}
+
+/// Loads modules along with their imports, recursively.
+/// Each source file is only loaded once, no matter how many modules import it.
+#[derive(Default)]
+pub struct Loader {
+    cache: HashMap<PathBuf, Rc<Module>>,
+    /// The modules being loaded, each importing the following one.
+    loading: Vec<PathBuf>,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the module at `path`, relative to the current directory.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<Rc<Module>, Error> {
+        let path = path.as_ref();
+        let path = fs::canonicalize(path).map_err(|source| Error::IO {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        if let Some(module) = self.cache.get(&path) {
+            return Ok(Rc::clone(module));
+        }
+        if let Some(pos) = self.loading.iter().position(|loading| loading == &path) {
+            let mut chain = self.loading[pos..].to_vec();
+            chain.push(path);
+            return Err(Error::Cycle(chain));
+        }
+
+        self.loading.push(path.clone());
+        let module = self.load_uncached(path);
+        self.loading.pop();
+        let module = Rc::new(module?);
+        self.cache.insert(module.path.clone(), Rc::clone(&module));
+        Ok(module)
+    }
+
+    fn load_uncached(&mut self, path: PathBuf) -> Result<Module, Error> {
+        let file = fs::File::open(&path).map_err(|source| Error::IO {
+            path: path.clone(),
+            source,
+        })?;
+        let mut tokens = Vec::new();
+        let mut spans = Vec::new();
+        for lexeme in Lexer::new(file).lexemes() {
+            let lexeme = match lexeme {
+                Ok(lexeme) => lexeme,
+                Err(source) => return Err(Error::Lexing { path, source }),
+            };
+            if let LexemeKind::Token(tok) = lexeme.kind {
+                tokens.push(tok);
+                spans.push(lexeme.span);
+            }
+        }
+        let ast = match Parser::new(tokens.iter()).with_spans(spans).parse() {
+            Ok(ast) => ast,
+            Err(source) => return Err(Error::Parsing { path, source }),
+        };
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut imports = HashMap::new();
+        let mut exports = HashMap::new();
+        for st in ast.statements() {
+            match st {
+                Statement::Import { path: import, alias } => {
+                    if imports.contains_key(alias) {
+                        return Err(Error::DuplicateImport {
+                            path,
+                            alias: alias.clone(),
+                        });
+                    }
+                    let module = self.load(dir.join(import)).map_err(|err| match err {
+                        // Both already tell which modules lead to the error.
+                        Error::Cycle(_) | Error::Import { .. } => err,
+                        err => Error::Import {
+                            chain: self.loading.clone(),
+                            source: Box::new(err),
+                        },
+                    })?;
+                    imports.insert(alias.clone(), module);
+                }
+                Statement::Export(st) => {
+                    if let Statement::Let {
+                        pattern: Pattern::Identifier(name),
+                        expression,
+                        ..
+                    } = st.as_ref()
+                    {
+                        if exports.insert(name.clone(), expression.clone()).is_some() {
+                            return Err(Error::DuplicateExport {
+                                path,
+                                name: name.clone(),
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Module {
+            path,
+            ast,
+            imports,
+            exports,
+        })
+    }
+}
@@ -0,0 +1,696 @@
+use std::mem;
+use std::ops::{Index, IndexMut};
+
+use super::{Expression, MatchArm, Parameter, Pattern, Statement, Type, AST};
+use crate::lexer::Token;
+
+/// Identifies a statement or an expression stored in an [`Arena`].
+///
+/// The parser assigns IDs as it stores the nodes, each after its children,
+/// and an ID is the position of its node in the arena: the position of the
+/// node in a post-order walk of the program. IDs are therefore the same every
+/// time a source is parsed, whether into an arena or into an [`AST`], and
+/// finding a node in an arena by its ID is a mere index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    pub fn new(index: usize) -> Self {
+        Self(index)
+    }
+
+    /// Returns the position of the node in the arena,
+    /// which can index a side table stored as a vector.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// Refers to an expression stored in an [`Arena`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ExprId(NodeId);
+
+impl ExprId {
+    pub fn id(self) -> NodeId {
+        self.0
+    }
+}
+
+/// Refers to a statement stored in an [`Arena`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StmtId(NodeId);
+
+impl StmtId {
+    pub fn id(self) -> NodeId {
+        self.0
+    }
+}
+
+/// A statement or an expression stored in an [`Arena`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    Statement(StmtNode),
+    Expression(ExprNode),
+}
+
+/// Stores the statements and expressions of a program in a single vector,
+/// so that parsing does not allocate a box for every operand.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Arena {
+    nodes: Vec<Node>,
+    /// Whether an expression was moved into a node stored after others,
+    /// so that the nodes are no longer in the order of a walk of the tree.
+    moved: bool,
+}
+
+impl Arena {
+    pub fn alloc(&mut self, exp: ExprNode) -> ExprId {
+        ExprId(self.push(Node::Expression(exp)))
+    }
+
+    pub fn alloc_statement(&mut self, st: StmtNode) -> StmtId {
+        StmtId(self.push(Node::Statement(st)))
+    }
+
+    fn push(&mut self, node: Node) -> NodeId {
+        self.nodes.push(node);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// Returns the number of statements and expressions in the arena.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the statement or expression with the given ID, if any.
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id.0)
+    }
+
+    /// Returns every statement and expression, along with its ID, in ID order.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.nodes.iter().enumerate().map(|(i, node)| (NodeId(i), node))
+    }
+
+    /// Notes that an expression was moved after other nodes were stored,
+    /// as a pipe does with its left operand.
+    pub(super) fn note_move(&mut self) {
+        self.moved = true;
+    }
+
+    /// Renumbers the nodes in the order of a post-order walk of the statements,
+    /// if an expression was moved. Otherwise, the nodes are already in that
+    /// order, as the parser stores every node after its children.
+    pub(super) fn renumber(&mut self, statements: &mut [StmtId]) {
+        if !mem::take(&mut self.moved) {
+            return;
+        }
+        let mut order = Vec::with_capacity(self.nodes.len());
+        for st in statements.iter() {
+            self.walk(st.0, &mut order);
+        }
+        let mut renumbered = vec![NodeId(usize::MAX); self.nodes.len()];
+        for (i, id) in order.iter().enumerate() {
+            renumbered[id.0] = NodeId(i);
+        }
+        let mut nodes: Vec<_> = mem::take(&mut self.nodes).into_iter().map(Some).collect();
+        self.nodes = order
+            .iter()
+            .map(|id| {
+                let mut node = nodes[id.0].take().expect("a node is walked once");
+                for_each_child(&mut node, &mut |child| *child = renumbered[child.0]);
+                node
+            })
+            .collect();
+        for st in statements {
+            st.0 = renumbered[st.0 .0];
+        }
+    }
+
+    /// Lists the node after its descendants, in the order of a post-order walk.
+    fn walk(&mut self, id: NodeId, order: &mut Vec<NodeId>) {
+        let mut children = Vec::new();
+        for_each_child(&mut self.nodes[id.0], &mut |child| children.push(*child));
+        for child in children {
+            self.walk(child, order);
+        }
+        order.push(id);
+    }
+
+    /// Builds the boxed form of the expression.
+    pub fn to_expression(&self, id: ExprId) -> Expression {
+        match &self[id] {
+            ExprNode::None => Expression::None,
+            ExprNode::Boolean(b) => Expression::Boolean(*b),
+            ExprNode::Identifier(name) => Expression::Identifier(name.clone()),
+            ExprNode::Integer(int) => Expression::Integer(*int),
+            ExprNode::String(s) => Expression::String(s.clone()),
+            ExprNode::Array(elems) => Expression::Array(self.to_expressions(elems)),
+            ExprNode::Hash(pairs) => Expression::Hash(
+                pairs
+                    .iter()
+                    .map(|&(key, value)| (self.to_expression(key), self.to_expression(value)))
+                    .collect(),
+            ),
+            ExprNode::Index { left, index } => Expression::Index {
+                left: self.to_boxed(*left),
+                index: self.to_boxed(*index),
+            },
+            ExprNode::Unary { operator, expression } => Expression::Unary {
+                operator: operator.clone(),
+                expression: self.to_boxed(*expression),
+            },
+            ExprNode::Binary { left, operator, right } => Expression::Binary {
+                left: self.to_boxed(*left),
+                operator: operator.clone(),
+                right: self.to_boxed(*right),
+            },
+            ExprNode::Logical { left, operator, right } => Expression::Logical {
+                left: self.to_boxed(*left),
+                operator: operator.clone(),
+                right: self.to_boxed(*right),
+            },
+            ExprNode::If { cond, conseq, altern } => Expression::If {
+                cond: self.to_boxed(*cond),
+                conseq: self.to_statements(conseq),
+                altern: altern.as_ref().map(|alt| self.to_statements(alt)),
+            },
+            ExprNode::Function {
+                name,
+                parameters,
+                rest,
+                ret,
+                body,
+            } => Expression::Function {
+                name: name.clone(),
+                parameters: parameters
+                    .iter()
+                    .map(|param| Parameter {
+                        name: param.name.clone(),
+                        annotation: param.annotation.clone(),
+                        default: param.default.map(|default| self.to_expression(default)),
+                    })
+                    .collect(),
+                rest: rest.clone(),
+                ret: ret.clone(),
+                body: self.to_statements(body),
+            },
+            ExprNode::Macro { parameters, body } => Expression::Macro {
+                parameters: parameters.clone(),
+                body: self.to_statements(body),
+            },
+            ExprNode::Call {
+                function,
+                arguments,
+                named,
+            } => Expression::Call {
+                function: self.to_boxed(*function),
+                arguments: self.to_expressions(arguments),
+                named: named
+                    .iter()
+                    .map(|(name, value)| (name.clone(), self.to_expression(*value)))
+                    .collect(),
+            },
+            ExprNode::Match { subject, arms } => Expression::Match {
+                subject: self.to_boxed(*subject),
+                arms: arms
+                    .iter()
+                    .map(|arm| MatchArm {
+                        pattern: arm.pattern.clone(),
+                        guard: arm.guard.map(|guard| self.to_expression(guard)),
+                        body: self.to_expression(arm.body),
+                    })
+                    .collect(),
+            },
+            ExprNode::Assign {
+                target,
+                operator,
+                value,
+            } => Expression::Assign {
+                target: self.to_boxed(*target),
+                operator: operator.clone(),
+                value: self.to_boxed(*value),
+            },
+        }
+    }
+
+    /// Builds the boxed form of the statement.
+    pub fn to_statement(&self, id: StmtId) -> Statement {
+        match &self[id] {
+            StmtNode::Expression(exp) => Statement::Expression(self.to_expression(*exp)),
+            StmtNode::Let {
+                pattern,
+                annotation,
+                expression,
+                doc,
+            } => Statement::Let {
+                pattern: pattern.clone(),
+                annotation: annotation.clone(),
+                expression: self.to_expression(*expression),
+                doc: doc.clone(),
+            },
+            StmtNode::Return(exp) => Statement::Return(self.to_expression(*exp)),
+            StmtNode::While { cond, body } => Statement::While {
+                cond: self.to_expression(*cond),
+                body: self.to_statements(body),
+            },
+            StmtNode::For {
+                variable,
+                iterable,
+                body,
+            } => Statement::For {
+                variable: variable.clone(),
+                iterable: self.to_expression(*iterable),
+                body: self.to_statements(body),
+            },
+            StmtNode::Break => Statement::Break,
+            StmtNode::Continue => Statement::Continue,
+            StmtNode::Import { path, alias } => Statement::Import {
+                path: path.clone(),
+                alias: alias.clone(),
+            },
+            StmtNode::Export(st) => Statement::Export(Box::new(self.to_statement(*st))),
+        }
+    }
+
+    pub fn to_statements(&self, statements: &[StmtId]) -> Vec<Statement> {
+        statements.iter().map(|&st| self.to_statement(st)).collect()
+    }
+
+    /// Builds the boxed form of the expression, moving
+    /// its nodes out of the arena instead of copying them.
+    pub fn into_expression(mut self, id: ExprId) -> Expression {
+        self.take_expression(id)
+    }
+
+    /// Moves the expression out of the arena, leaving [`ExprNode::None`] behind.
+    fn take_expression(&mut self, id: ExprId) -> Expression {
+        match mem::replace(&mut self[id], ExprNode::None) {
+            ExprNode::None => Expression::None,
+            ExprNode::Boolean(b) => Expression::Boolean(b),
+            ExprNode::Identifier(name) => Expression::Identifier(name),
+            ExprNode::Integer(int) => Expression::Integer(int),
+            ExprNode::String(s) => Expression::String(s),
+            ExprNode::Array(elems) => Expression::Array(self.take_expressions(elems)),
+            ExprNode::Hash(pairs) => Expression::Hash(
+                pairs
+                    .into_iter()
+                    .map(|(key, value)| (self.take_expression(key), self.take_expression(value)))
+                    .collect(),
+            ),
+            ExprNode::Index { left, index } => Expression::Index {
+                left: self.take_boxed(left),
+                index: self.take_boxed(index),
+            },
+            ExprNode::Unary { operator, expression } => Expression::Unary {
+                operator,
+                expression: self.take_boxed(expression),
+            },
+            ExprNode::Binary { left, operator, right } => Expression::Binary {
+                left: self.take_boxed(left),
+                operator,
+                right: self.take_boxed(right),
+            },
+            ExprNode::Logical { left, operator, right } => Expression::Logical {
+                left: self.take_boxed(left),
+                operator,
+                right: self.take_boxed(right),
+            },
+            ExprNode::If { cond, conseq, altern } => Expression::If {
+                cond: self.take_boxed(cond),
+                conseq: self.take_statements(conseq),
+                altern: altern.map(|alt| self.take_statements(alt)),
+            },
+            ExprNode::Function {
+                name,
+                parameters,
+                rest,
+                ret,
+                body,
+            } => Expression::Function {
+                name,
+                parameters: parameters
+                    .into_iter()
+                    .map(|param| Parameter {
+                        name: param.name,
+                        annotation: param.annotation,
+                        default: param.default.map(|default| self.take_expression(default)),
+                    })
+                    .collect(),
+                rest,
+                ret,
+                body: self.take_statements(body),
+            },
+            ExprNode::Macro { parameters, body } => Expression::Macro {
+                parameters,
+                body: self.take_statements(body),
+            },
+            ExprNode::Call {
+                function,
+                arguments,
+                named,
+            } => Expression::Call {
+                function: self.take_boxed(function),
+                arguments: self.take_expressions(arguments),
+                named: named
+                    .into_iter()
+                    .map(|(name, value)| (name, self.take_expression(value)))
+                    .collect(),
+            },
+            ExprNode::Match { subject, arms } => Expression::Match {
+                subject: self.take_boxed(subject),
+                arms: arms
+                    .into_iter()
+                    .map(|arm| MatchArm {
+                        pattern: arm.pattern,
+                        guard: arm.guard.map(|guard| self.take_expression(guard)),
+                        body: self.take_expression(arm.body),
+                    })
+                    .collect(),
+            },
+            ExprNode::Assign {
+                target,
+                operator,
+                value,
+            } => Expression::Assign {
+                target: self.take_boxed(target),
+                operator,
+                value: self.take_boxed(value),
+            },
+        }
+    }
+
+    /// Moves the statement out of the arena, leaving [`StmtNode::Break`] behind.
+    fn take_statement(&mut self, id: StmtId) -> Statement {
+        match mem::replace(&mut self[id], StmtNode::Break) {
+            StmtNode::Expression(exp) => Statement::Expression(self.take_expression(exp)),
+            StmtNode::Let {
+                pattern,
+                annotation,
+                expression,
+                doc,
+            } => Statement::Let {
+                pattern,
+                annotation,
+                expression: self.take_expression(expression),
+                doc,
+            },
+            StmtNode::Return(exp) => Statement::Return(self.take_expression(exp)),
+            StmtNode::While { cond, body } => Statement::While {
+                cond: self.take_expression(cond),
+                body: self.take_statements(body),
+            },
+            StmtNode::For {
+                variable,
+                iterable,
+                body,
+            } => Statement::For {
+                variable,
+                iterable: self.take_expression(iterable),
+                body: self.take_statements(body),
+            },
+            StmtNode::Break => Statement::Break,
+            StmtNode::Continue => Statement::Continue,
+            StmtNode::Import { path, alias } => Statement::Import { path, alias },
+            StmtNode::Export(st) => Statement::Export(Box::new(self.take_statement(st))),
+        }
+    }
+
+    fn take_statements(&mut self, statements: Vec<StmtId>) -> Vec<Statement> {
+        statements.into_iter().map(|st| self.take_statement(st)).collect()
+    }
+
+    fn take_boxed(&mut self, id: ExprId) -> Box<Expression> {
+        Box::new(self.take_expression(id))
+    }
+
+    fn take_expressions(&mut self, ids: Vec<ExprId>) -> Vec<Expression> {
+        ids.into_iter().map(|id| self.take_expression(id)).collect()
+    }
+
+    fn to_boxed(&self, id: ExprId) -> Box<Expression> {
+        Box::new(self.to_expression(id))
+    }
+
+    fn to_expressions(&self, ids: &[ExprId]) -> Vec<Expression> {
+        ids.iter().map(|&id| self.to_expression(id)).collect()
+    }
+}
+
+/// Calls `f` with the ID of each child of the node, in the order
+/// the [`Visitor`](super::visit::Visitor) walks them.
+fn for_each_child(node: &mut Node, f: &mut impl FnMut(&mut NodeId)) {
+    let mut expression = |ExprId(id): &mut ExprId| f(id);
+    match node {
+        Node::Expression(exp) => match exp {
+            ExprNode::Array(elems) => elems.iter_mut().for_each(expression),
+            ExprNode::Hash(pairs) => {
+                for (key, value) in pairs {
+                    expression(key);
+                    expression(value);
+                }
+            }
+            ExprNode::Index { left, index: right }
+            | ExprNode::Binary { left, right, .. }
+            | ExprNode::Logical { left, right, .. }
+            | ExprNode::Assign {
+                target: left,
+                value: right,
+                ..
+            } => {
+                expression(left);
+                expression(right);
+            }
+            ExprNode::Unary { expression: exp, .. } => expression(exp),
+            ExprNode::If { cond, conseq, altern } => {
+                expression(cond);
+                conseq.iter_mut().chain(altern.iter_mut().flatten()).for_each(|StmtId(id)| f(id));
+            }
+            ExprNode::Function { parameters, body, .. } => {
+                parameters.iter_mut().filter_map(|param| param.default.as_mut()).for_each(expression);
+                body.iter_mut().for_each(|StmtId(id)| f(id));
+            }
+            ExprNode::Macro { body, .. } => body.iter_mut().for_each(|StmtId(id)| f(id)),
+            ExprNode::Call {
+                function,
+                arguments,
+                named,
+            } => {
+                expression(function);
+                arguments.iter_mut().chain(named.iter_mut().map(|(_, arg)| arg)).for_each(expression);
+            }
+            ExprNode::Match { subject, arms } => {
+                expression(subject);
+                for arm in arms {
+                    arm.guard.iter_mut().chain([&mut arm.body]).for_each(&mut expression);
+                }
+            }
+            ExprNode::None | ExprNode::Boolean(_) | ExprNode::Identifier(_) | ExprNode::Integer(_) | ExprNode::String(_) => {}
+        },
+        Node::Statement(st) => match st {
+            StmtNode::Expression(exp) | StmtNode::Return(exp) | StmtNode::Let { expression: exp, .. } => expression(exp),
+            StmtNode::While { cond: exp, body } | StmtNode::For { iterable: exp, body, .. } => {
+                expression(exp);
+                body.iter_mut().for_each(|StmtId(id)| f(id));
+            }
+            StmtNode::Export(StmtId(id)) => f(id),
+            StmtNode::Break | StmtNode::Continue | StmtNode::Import { .. } => {}
+        },
+    }
+}
+
+impl Index<ExprId> for Arena {
+    type Output = ExprNode;
+
+    fn index(&self, id: ExprId) -> &ExprNode {
+        match &self.nodes[id.0 .0] {
+            Node::Expression(exp) => exp,
+            Node::Statement(_) => unreachable!("an expression ID refers to an expression"),
+        }
+    }
+}
+
+impl IndexMut<ExprId> for Arena {
+    fn index_mut(&mut self, id: ExprId) -> &mut ExprNode {
+        match &mut self.nodes[id.0 .0] {
+            Node::Expression(exp) => exp,
+            Node::Statement(_) => unreachable!("an expression ID refers to an expression"),
+        }
+    }
+}
+
+impl Index<StmtId> for Arena {
+    type Output = StmtNode;
+
+    fn index(&self, id: StmtId) -> &StmtNode {
+        match &self.nodes[id.0 .0] {
+            Node::Statement(st) => st,
+            Node::Expression(_) => unreachable!("a statement ID refers to a statement"),
+        }
+    }
+}
+
+impl IndexMut<StmtId> for Arena {
+    fn index_mut(&mut self, id: StmtId) -> &mut StmtNode {
+        match &mut self.nodes[id.0 .0] {
+            Node::Statement(st) => st,
+            Node::Expression(_) => unreachable!("a statement ID refers to a statement"),
+        }
+    }
+}
+
+/// An [`Expression`] whose sub-expressions are stored in an [`Arena`].
+/// Other [`Builder`](super::Builder)s make nodes whose children are of another type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExprNode<E = ExprId, S = StmtId> {
+    None,
+    Boolean(bool),
+    Identifier(String),
+    Integer(i64),
+    String(String),
+    Array(Vec<E>),
+    Hash(Vec<(E, E)>),
+    Index {
+        left: E,
+        index: E,
+    },
+    Unary {
+        operator: Token,
+        expression: E,
+    },
+    Binary {
+        left: E,
+        operator: Token,
+        right: E,
+    },
+    Logical {
+        left: E,
+        operator: Token,
+        right: E,
+    },
+    If {
+        cond: E,
+        conseq: Vec<S>,
+        altern: Option<Vec<S>>,
+    },
+    Function {
+        name: Option<String>,
+        parameters: Vec<ParamNode<E>>,
+        rest: Option<String>,
+        ret: Option<Type>,
+        body: Vec<S>,
+    },
+    Macro {
+        parameters: Vec<String>,
+        body: Vec<S>,
+    },
+    Call {
+        function: E,
+        arguments: Vec<E>,
+        named: Vec<(String, E)>,
+    },
+    Match {
+        subject: E,
+        arms: Vec<ArmNode<E>>,
+    },
+    Assign {
+        target: E,
+        operator: Option<Token>,
+        value: E,
+    },
+}
+
+/// A [`Statement`] whose expressions are stored in an [`Arena`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StmtNode<E = ExprId, S = StmtId> {
+    Expression(E),
+    Let {
+        pattern: Pattern,
+        annotation: Option<Type>,
+        expression: E,
+        doc: Option<String>,
+    },
+    Return(E),
+    While {
+        cond: E,
+        body: Vec<S>,
+    },
+    For {
+        variable: String,
+        iterable: E,
+        body: Vec<S>,
+    },
+    Break,
+    Continue,
+    Import {
+        path: String,
+        alias: String,
+    },
+    Export(S),
+}
+
+/// A [`MatchArm`] whose expressions are stored in an [`Arena`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArmNode<E = ExprId> {
+    pub pattern: Pattern,
+    pub guard: Option<E>,
+    pub body: E,
+}
+
+/// A [`Parameter`] whose default value is stored in an [`Arena`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParamNode<E = ExprId> {
+    pub name: String,
+    pub annotation: Option<Type>,
+    pub default: Option<E>,
+}
+
+/// A program whose expressions are stored in an [`Arena`],
+/// as returned by [`Parser::parse_arena`](super::Parser::parse_arena).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArenaAST {
+    arena: Arena,
+    statements: Vec<StmtId>,
+}
+
+impl ArenaAST {
+    pub fn new(arena: Arena, statements: Vec<StmtId>) -> Self {
+        Self { arena, statements }
+    }
+
+    pub fn arena(&self) -> &Arena {
+        &self.arena
+    }
+
+    /// Returns the top-level statements, whose nodes are in the arena.
+    pub fn statements(&self) -> &[StmtId] {
+        &self.statements
+    }
+
+    /// Returns the statement or expression with the given ID, if any.
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.arena.node(id)
+    }
+
+    /// Returns every statement and expression, along with its ID, in ID order.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.arena.nodes()
+    }
+
+    /// Builds the boxed form of the program.
+    pub fn to_ast(&self) -> AST {
+        AST::new(self.arena.to_statements(&self.statements))
+    }
+}
+
+impl From<ArenaAST> for AST {
+    /// Builds the boxed form of the program, moving the
+    /// nodes out of the arena instead of copying them.
+    fn from(mut ast: ArenaAST) -> Self {
+        AST::new(ast.arena.take_statements(ast.statements))
+    }
+}
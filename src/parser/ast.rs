@@ -1,26 +1,88 @@
 use std::fmt::Debug;
 
+use super::visit::{self, Visitor};
+use super::NodeId;
 use crate::lexer::Token;
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Statement {
     /// An expression statement. Although it may sound confusing,
     /// a statement like `5 + 10;` is legal in Monkey.
     Expression(Expression),
 
+    /// Binds the value of `expression` to the names in `pattern`,
+    /// optionally annotated with the type of the value.
     Let {
-        identifier: String,
+        pattern: Pattern,
+        annotation: Option<Type>,
         expression: Expression,
+        /// The doc comment preceding the statement, one line per `///`.
+        doc: Option<String>,
     },
     Return(Expression),
+    While {
+        cond: Expression,
+        body: Vec<Statement>,
+    },
+    /// Iterates over `iterable`, binding each element to `variable`.
+    For {
+        variable: String,
+        iterable: Expression,
+        body: Vec<Statement>,
+    },
+    Break,
+    Continue,
+    /// Loads the module at `path`, relative to the importing file,
+    /// and binds its exports to `alias`.
+    Import {
+        path: String,
+        alias: String,
+    },
+    /// Makes the binding of a let statement visible to importing modules.
+    Export(Box<Statement>),
 }
 
 impl Debug for Statement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Statement::Expression(exp) => write!(f, "{:?}", exp),
-            Statement::Let { identifier, expression } => write!(f, "{} {} = {:?}", Token::Let, identifier, expression),
+            Statement::Let {
+                pattern,
+                annotation,
+                expression,
+                doc,
+            } => {
+                if let Some(doc) = doc {
+                    for line in doc.lines() {
+                        writeln!(f, "{}", Token::DocComment(line.to_string()))?;
+                    }
+                }
+                write!(f, "{} {:?}", Token::Let, pattern)?;
+                if let Some(annotation) = annotation {
+                    write!(f, "{} {:?}", Token::Colon, annotation)?;
+                }
+                write!(f, " = {:?}", expression)
+            }
             Statement::Return(exp) => write!(f, "{} {:?}", Token::Return, exp),
+            Statement::While { cond, body } => {
+                write!(f, "{} {:?} ", Token::While, cond)?;
+                for st in body {
+                    write!(f, "{:?}", st)?;
+                }
+                Ok(())
+            }
+            Statement::For { variable, iterable, body } => {
+                write!(f, "{} {} {} {:?} ", Token::For, variable, Token::In, iterable)?;
+                for st in body {
+                    write!(f, "{:?}", st)?;
+                }
+                Ok(())
+            }
+            Statement::Break => write!(f, "{}", Token::Break),
+            Statement::Continue => write!(f, "{}", Token::Continue),
+            Statement::Import { path, alias } => write!(f, "{} {:?} {} {}", Token::Import, path, Token::As, alias),
+            Statement::Export(st) => write!(f, "{} {:?}", Token::Export, st),
         }
     }
 }
@@ -28,6 +90,10 @@ impl Debug for Statement {
 #[derive(PartialEq, PartialOrd)]
 pub enum Priority {
     Lowest,
+    Assign,
+    Pipe,
+    Or,
+    And,
     Equals,
     LessOrGreaterThan,
     Sum,
@@ -44,17 +110,33 @@ impl Priority {
             Token::LessThan | Token::GreaterThan => Self::LessOrGreaterThan,
             Token::Plus | Token::Minus => Self::Sum,
             Token::Equal | Token::NotEqual => Self::Equals,
+            Token::And => Self::And,
+            Token::Or => Self::Or,
+            Token::LeftParen | Token::LeftBracket => Self::Call,
+            Token::Pipe => Self::Pipe,
+            Token::Assign | Token::PlusAssign | Token::MinusAssign | Token::AsteriskAssign | Token::SlashAssign => {
+                Self::Assign
+            }
             _ => Self::Lowest,
         }
     }
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expression {
     None,
     Boolean(bool),
     Identifier(String),
     Integer(i64),
+    String(String),
+    Array(Vec<Expression>),
+    /// A hash literal, with its key-value pairs in source order.
+    Hash(Vec<(Expression, Expression)>),
+    Index {
+        left: Box<Expression>,
+        index: Box<Expression>,
+    },
     Unary {
         operator: Token,
         expression: Box<Expression>,
@@ -64,11 +146,57 @@ pub enum Expression {
         operator: Token,
         right: Box<Expression>,
     },
+    /// A `&&` or `||` expression. Unlike a binary expression,
+    /// its right operand is not evaluated if the left one decides the result.
+    Logical {
+        left: Box<Expression>,
+        operator: Token,
+        right: Box<Expression>,
+    },
     If {
         cond: Box<Expression>,
         conseq: Vec<Statement>,
         altern: Option<Vec<Statement>>,
     },
+    /// A function literal, optionally annotated with its return type.
+    Function {
+        /// The name the function was declared with, as in `fn add(a, b) { a + b }`.
+        /// Anonymous functions have none.
+        name: Option<String>,
+        parameters: Vec<Parameter>,
+        /// Collects the arguments left after `parameters` into an array, written `...rest`.
+        rest: Option<String>,
+        ret: Option<Type>,
+        body: Vec<Statement>,
+    },
+    /// A macro literal. Calls to macros are replaced with the code
+    /// they return before evaluation.
+    Macro {
+        parameters: Vec<String>,
+        body: Vec<Statement>,
+    },
+    Call {
+        function: Box<Expression>,
+        arguments: Vec<Expression>,
+        /// Arguments passed by parameter name, written `name: value`
+        /// after the positional ones.
+        named: Vec<(String, Expression)>,
+    },
+    /// Evaluates the body of the first arm whose pattern matches `subject`
+    /// and whose guard, if any, holds.
+    Match {
+        subject: Box<Expression>,
+        arms: Vec<MatchArm>,
+    },
+    /// Assigns to an existing binding or to an index of a collection.
+    /// `operator` is the binary operator of a compound assignment, e.g.
+    /// [`Token::Plus`] for `x += 1`, which combines the current value of
+    /// `target` with `value`. The target is evaluated only once.
+    Assign {
+        target: Box<Expression>,
+        operator: Option<Token>,
+        value: Box<Expression>,
+    },
 }
 
 impl Debug for Expression {
@@ -79,8 +207,33 @@ impl Debug for Expression {
             Expression::Boolean(b) => write!(f, "{}", b),
             Expression::Identifier(name) => write!(f, "{}", name),
             Expression::Integer(int) => write!(f, "{}", int),
+            Expression::String(s) => write!(f, "{:?}", s),
+            Expression::Array(elems) => {
+                write!(f, "{}", Token::LeftBracket)?;
+                for (i, elem) in elems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{:?}", elem)?;
+                }
+                write!(f, "{}", Token::RightBracket)
+            }
+            Expression::Hash(pairs) => {
+                write!(f, "{}", Token::LeftBrace)?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{:?}{} {:?}", key, Token::Colon, value)?;
+                }
+                write!(f, "{}", Token::RightBrace)
+            }
+            Expression::Index { left, index } => {
+                write!(f, "{:?}{}{:?}{}", left, Token::LeftBracket, index, Token::RightBracket)
+            }
             Expression::Unary { operator, expression } => write!(f, "{}{:?}", operator, expression),
             Expression::Binary { left, operator, right } => write!(f, "{:?}{}{:?}", left, operator, right),
+            Expression::Logical { left, operator, right } => write!(f, "{:?}{}{:?}", left, operator, right),
             Expression::If { cond, conseq, altern } => {
                 write!(f, "{} {:?} ", Token::If, cond)?;
                 for st in conseq {
@@ -94,12 +247,242 @@ impl Debug for Expression {
                 }
                 Ok(())
             }
+            Expression::Function {
+                name,
+                parameters,
+                rest,
+                ret,
+                body,
+            } => {
+                write!(f, "{}", Token::Function)?;
+                if let Some(name) = name {
+                    write!(f, " {}", name)?;
+                }
+                write!(f, "{}", Token::LeftParen)?;
+                for (i, param) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{:?}", param)?;
+                }
+                if let Some(rest) = rest {
+                    if !parameters.is_empty() {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{}{}", Token::Ellipsis, rest)?;
+                }
+                write!(f, "{}", Token::RightParen)?;
+                if let Some(ret) = ret {
+                    write!(f, " {} {:?}", Token::Arrow, ret)?;
+                }
+                write!(f, " ")?;
+                for st in body {
+                    write!(f, "{:?}", st)?;
+                }
+                Ok(())
+            }
+            Expression::Macro { parameters, body } => {
+                write!(f, "{}{}{}{} ", Token::Macro, Token::LeftParen, parameters.join(", "), Token::RightParen)?;
+                for st in body {
+                    write!(f, "{:?}", st)?;
+                }
+                Ok(())
+            }
+            Expression::Call {
+                function,
+                arguments,
+                named,
+            } => {
+                write!(f, "{:?}{}", function, Token::LeftParen)?;
+                for (i, arg) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{:?}", arg)?;
+                }
+                for (i, (name, arg)) in named.iter().enumerate() {
+                    if i > 0 || !arguments.is_empty() {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{}{} {:?}", name, Token::Colon, arg)?;
+                }
+                write!(f, "{}", Token::RightParen)
+            }
+            Expression::Match { subject, arms } => {
+                write!(f, "{} {:?} ", Token::Match, subject)?;
+                for (i, arm) in arms.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{:?}", arm)?;
+                }
+                Ok(())
+            }
+            Expression::Assign {
+                target,
+                operator: Some(operator),
+                value,
+            } => write!(f, "{:?}{}{}{:?}", target, operator, Token::Assign, value),
+            Expression::Assign { target, value, .. } => write!(f, "{:?}{}{:?}", target, Token::Assign, value),
         }?;
         write!(f, ")")
     }
 }
 
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Parameter {
+    pub name: String,
+    pub annotation: Option<Type>,
+    /// The value of the parameter when a call does not pass it, written `name = value`.
+    pub default: Option<Expression>,
+}
+
+impl Debug for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(annotation) = &self.annotation {
+            write!(f, "{} {:?}", Token::Colon, annotation)?;
+        }
+        if let Some(default) = &self.default {
+            write!(f, " {} {:?}", Token::Assign, default)?;
+        }
+        Ok(())
+    }
+}
+
+/// A type annotation.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Type {
+    /// A type referred to by its name, like `int`, `bool` or `string`.
+    Named(String),
+    /// An array whose elements all have the same type, written `[T]`.
+    Array(Box<Type>),
+    /// A hash whose keys and values have the same types, written `{K: V}`.
+    Hash(Box<Type>, Box<Type>),
+    /// A function, written `fn(A, B) -> R`.
+    Function { parameters: Vec<Type>, ret: Box<Type> },
+}
+
+impl Debug for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Array(elem) => write!(f, "{}{:?}{}", Token::LeftBracket, elem, Token::RightBracket),
+            Type::Hash(key, value) => write!(f, "{}{:?}{} {:?}{}", Token::LeftBrace, key, Token::Colon, value, Token::RightBrace),
+            Type::Function { parameters, ret } => {
+                write!(f, "{}{}", Token::Function, Token::LeftParen)?;
+                for (i, param) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{:?}", param)?;
+                }
+                write!(f, "{} {} {:?}", Token::RightParen, Token::Arrow, ret)
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expression>,
+    pub body: Expression,
+}
+
+impl Debug for MatchArm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} ", self.pattern)?;
+        if let Some(guard) = &self.guard {
+            write!(f, "{} {:?} ", Token::If, guard)?;
+        }
+        write!(f, "{} {:?}", Token::FatArrow, self.body)
+    }
+}
+
+/// The shape a value is tested against in a match arm or destructured into by a let statement.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Pattern {
+    /// Matches anything without binding it, written `_`.
+    Wildcard,
+    /// Matches anything and binds it to the name.
+    Identifier(String),
+    Boolean(bool),
+    Integer(i64),
+    String(String),
+    /// Matches an array whose first elements match `elements`. The remaining
+    /// elements are bound as an array to `rest`, if any. Otherwise, the
+    /// array must have exactly as many elements as there are patterns.
+    Array {
+        elements: Vec<Pattern>,
+        rest: Option<String>,
+    },
+    /// Matches a hash having all the literal keys, whose values match the patterns.
+    Hash(Vec<(Expression, Pattern)>),
+}
+
+impl Pattern {
+    /// Returns whether the pattern matches any value.
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Self::Wildcard | Self::Identifier(_))
+    }
+
+    /// Returns whether the pattern, or any pattern nested in it,
+    /// is a literal that the value must be equal to.
+    pub fn has_literal(&self) -> bool {
+        match self {
+            Self::Wildcard | Self::Identifier(_) => false,
+            Self::Boolean(_) | Self::Integer(_) | Self::String(_) => true,
+            Self::Array { elements, .. } => elements.iter().any(Self::has_literal),
+            Self::Hash(pairs) => pairs.iter().any(|(_, pattern)| pattern.has_literal()),
+        }
+    }
+}
+
+impl Debug for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Identifier(name) => write!(f, "{}", name),
+            Pattern::Boolean(b) => write!(f, "{}", b),
+            Pattern::Integer(int) => write!(f, "{}", int),
+            Pattern::String(s) => write!(f, "{:?}", s),
+            Pattern::Array { elements, rest } => {
+                write!(f, "{}", Token::LeftBracket)?;
+                for (i, elem) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{:?}", elem)?;
+                }
+                if let Some(rest) = rest {
+                    if !elements.is_empty() {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{}{}", Token::Ellipsis, rest)?;
+                }
+                write!(f, "{}", Token::RightBracket)
+            }
+            Pattern::Hash(pairs) => {
+                write!(f, "{}", Token::LeftBrace)?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{:?}{} {:?}", key, Token::Colon, value)?;
+                }
+                write!(f, "{}", Token::RightBrace)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AST {
     statements: Vec<Statement>,
 }
@@ -112,4 +495,54 @@ impl AST {
     pub fn push(&mut self, st: Statement) {
         self.statements.push(st);
     }
+
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    pub fn statements_mut(&mut self) -> &mut Vec<Statement> {
+        &mut self.statements
+    }
+
+    /// Returns every statement and expression, along with its ID, in ID order.
+    ///
+    /// A node's ID is its position in a post-order walk of the program,
+    /// children first, which is the order the parser stores the nodes in.
+    /// A node therefore has the same ID here as in the [`ArenaAST`](super::ArenaAST)
+    /// of the same source, so side tables can be shared between both forms.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, NodeRef<'_>)> {
+        let mut collector = NodeCollector::default();
+        collector.visit_block(&self.statements);
+        collector.nodes.into_iter().enumerate().map(|(i, node)| (NodeId::new(i), node))
+    }
+
+    /// Returns the statement or expression with the given ID, if any.
+    pub fn node(&self, id: NodeId) -> Option<NodeRef<'_>> {
+        self.nodes().nth(id.index()).map(|(_, node)| node)
+    }
+}
+
+/// A statement or an expression of an [`AST`], as found by its [`NodeId`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeRef<'a> {
+    Statement(&'a Statement),
+    Expression(&'a Expression),
+}
+
+/// Lists the nodes in post-order.
+#[derive(Default)]
+struct NodeCollector<'a> {
+    nodes: Vec<NodeRef<'a>>,
+}
+
+impl<'a> Visitor<'a> for NodeCollector<'a> {
+    fn visit_statement(&mut self, st: &'a Statement) {
+        visit::walk_statement(self, st);
+        self.nodes.push(NodeRef::Statement(st));
+    }
+
+    fn visit_expression(&mut self, exp: &'a Expression) {
+        visit::walk_expression(self, exp);
+        self.nodes.push(NodeRef::Expression(exp));
+    }
 }
@@ -0,0 +1,206 @@
+use super::{Arena, ArmNode, ExprId, ExprNode, Expression, MatchArm, ParamNode, Parameter, Statement, StmtId, StmtNode};
+
+/// Makes the tree built by a [`Parser`](super::Parser), one node at a time,
+/// each after its children.
+///
+/// [`Arena`] stores the nodes in a vector and refers to them by index,
+/// while [`Boxed`] makes the [`Expression`]s and [`Statement`]s themselves.
+pub trait Builder: Default {
+    /// What an expression is referred to by in its parent.
+    type Expr;
+    /// What a statement is referred to by in its parent.
+    type Stmt;
+
+    fn expression(&mut self, node: ExprNode<Self::Expr, Self::Stmt>) -> Self::Expr;
+
+    fn statement(&mut self, node: StmtNode<Self::Expr, Self::Stmt>) -> Self::Stmt;
+
+    /// Tells whether the expression is an `if` or a `match`, which end with a block.
+    /// Function, macro and hash literals end with a brace too, but are values
+    /// rather than blocks, so statements made of them still need a separator.
+    fn ends_with_brace(&self, exp: &Self::Expr) -> bool;
+
+    /// Tells whether the expression is an identifier or an index, which can be assigned to.
+    fn is_assignable(&self, exp: &Self::Expr) -> bool;
+
+    /// Returns the positional arguments of the expression, if it is a call.
+    fn arguments_mut<'e>(&'e mut self, exp: &'e mut Self::Expr) -> Option<&'e mut Vec<Self::Expr>>;
+
+    /// Builds the boxed form of the expression, as errors hold it.
+    fn to_expression(&self, exp: &Self::Expr) -> Expression;
+}
+
+impl Builder for Arena {
+    type Expr = ExprId;
+    type Stmt = StmtId;
+
+    fn expression(&mut self, node: ExprNode) -> ExprId {
+        self.alloc(node)
+    }
+
+    fn statement(&mut self, node: StmtNode) -> StmtId {
+        self.alloc_statement(node)
+    }
+
+    fn ends_with_brace(&self, &exp: &ExprId) -> bool {
+        matches!(self[exp], ExprNode::If { .. } | ExprNode::Match { .. })
+    }
+
+    fn is_assignable(&self, &exp: &ExprId) -> bool {
+        matches!(self[exp], ExprNode::Identifier(_) | ExprNode::Index { .. })
+    }
+
+    fn arguments_mut<'e>(&'e mut self, &mut exp: &'e mut ExprId) -> Option<&'e mut Vec<ExprId>> {
+        // The caller inserts an expression stored before the call among its arguments.
+        self.note_move();
+        match &mut self[exp] {
+            ExprNode::Call { arguments, .. } => Some(arguments),
+            _ => None,
+        }
+    }
+
+    fn to_expression(&self, &exp: &ExprId) -> Expression {
+        Arena::to_expression(self, exp)
+    }
+}
+
+/// Builds the boxed [`AST`](super::AST) directly, as [`Parser::parse`](super::Parser::parse) does.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Boxed;
+
+impl Builder for Boxed {
+    type Expr = Expression;
+    type Stmt = Statement;
+
+    fn expression(&mut self, node: ExprNode<Expression, Statement>) -> Expression {
+        match node {
+            ExprNode::None => Expression::None,
+            ExprNode::Boolean(b) => Expression::Boolean(b),
+            ExprNode::Identifier(name) => Expression::Identifier(name),
+            ExprNode::Integer(int) => Expression::Integer(int),
+            ExprNode::String(s) => Expression::String(s),
+            ExprNode::Array(elems) => Expression::Array(elems),
+            ExprNode::Hash(pairs) => Expression::Hash(pairs),
+            ExprNode::Index { left, index } => Expression::Index {
+                left: Box::new(left),
+                index: Box::new(index),
+            },
+            ExprNode::Unary { operator, expression } => Expression::Unary {
+                operator,
+                expression: Box::new(expression),
+            },
+            ExprNode::Binary { left, operator, right } => Expression::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            },
+            ExprNode::Logical { left, operator, right } => Expression::Logical {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            },
+            ExprNode::If { cond, conseq, altern } => Expression::If {
+                cond: Box::new(cond),
+                conseq,
+                altern,
+            },
+            ExprNode::Function {
+                name,
+                parameters,
+                rest,
+                ret,
+                body,
+            } => Expression::Function {
+                name,
+                parameters: parameters
+                    .into_iter()
+                    .map(|ParamNode { name, annotation, default }| Parameter {
+                        name,
+                        annotation,
+                        default,
+                    })
+                    .collect(),
+                rest,
+                ret,
+                body,
+            },
+            ExprNode::Macro { parameters, body } => Expression::Macro { parameters, body },
+            ExprNode::Call {
+                function,
+                arguments,
+                named,
+            } => Expression::Call {
+                function: Box::new(function),
+                arguments,
+                named,
+            },
+            ExprNode::Match { subject, arms } => Expression::Match {
+                subject: Box::new(subject),
+                arms: arms
+                    .into_iter()
+                    .map(|ArmNode { pattern, guard, body }| MatchArm { pattern, guard, body })
+                    .collect(),
+            },
+            ExprNode::Assign {
+                target,
+                operator,
+                value,
+            } => Expression::Assign {
+                target: Box::new(target),
+                operator,
+                value: Box::new(value),
+            },
+        }
+    }
+
+    fn statement(&mut self, node: StmtNode<Expression, Statement>) -> Statement {
+        match node {
+            StmtNode::Expression(exp) => Statement::Expression(exp),
+            StmtNode::Let {
+                pattern,
+                annotation,
+                expression,
+                doc,
+            } => Statement::Let {
+                pattern,
+                annotation,
+                expression,
+                doc,
+            },
+            StmtNode::Return(exp) => Statement::Return(exp),
+            StmtNode::While { cond, body } => Statement::While { cond, body },
+            StmtNode::For {
+                variable,
+                iterable,
+                body,
+            } => Statement::For {
+                variable,
+                iterable,
+                body,
+            },
+            StmtNode::Break => Statement::Break,
+            StmtNode::Continue => Statement::Continue,
+            StmtNode::Import { path, alias } => Statement::Import { path, alias },
+            StmtNode::Export(st) => Statement::Export(Box::new(st)),
+        }
+    }
+
+    fn ends_with_brace(&self, exp: &Expression) -> bool {
+        matches!(exp, Expression::If { .. } | Expression::Match { .. })
+    }
+
+    fn is_assignable(&self, exp: &Expression) -> bool {
+        matches!(exp, Expression::Identifier(_) | Expression::Index { .. })
+    }
+
+    fn arguments_mut<'e>(&'e mut self, exp: &'e mut Expression) -> Option<&'e mut Vec<Expression>> {
+        match exp {
+            Expression::Call { arguments, .. } => Some(arguments),
+            _ => None,
+        }
+    }
+
+    fn to_expression(&self, exp: &Expression) -> Expression {
+        exp.clone()
+    }
+}
@@ -1,12 +1,186 @@
 use std::fmt;
 
-use crate::lexer::Token;
+use super::{Expression, Pattern};
+use crate::lexer::{Span, Token};
 
 #[derive(thiserror::Error, fmt::Debug)]
 pub enum Error {
-    #[error("expected {expected}, got {got}")]
-    ExpectedToken { expected: Token, got: Token },
+    /// A token is none of those the grammar allows where it appears.
+    #[error("{0}")]
+    ExpectedToken(Box<Mismatch>),
 
-    #[error("reached the end of the source")]
-    EOF,
+    /// The target of an assignment is neither a name nor an index expression.
+    /// `span` locates the target, if the parser was given spans.
+    #[error("cannot assign to {target:?}")]
+    InvalidAssignmentTarget { target: Box<Expression>, span: Option<Span> },
+
+    /// A match arm follows one that matches anything.
+    /// `span` locates its pattern, if the parser was given spans.
+    #[error("unreachable match arm {pattern:?}")]
+    UnreachableArm { pattern: Box<Pattern>, span: Option<Span> },
+
+    /// `span` locates the exported statement, if the parser was given spans.
+    #[error("only single bindings can be exported, got {pattern:?}")]
+    ExportPattern { pattern: Box<Pattern>, span: Option<Span> },
+
+    /// A `let` pattern contains a literal, which could fail to match.
+    /// `span` locates the pattern, if the parser was given spans.
+    #[error("let can only destructure and bind, got the literal pattern {pattern:?}")]
+    LiteralPattern { pattern: Box<Pattern>, span: Option<Span> },
+
+    /// `span` locates the name of the parameter, if the parser was given spans.
+    #[error("parameter {name} follows the rest parameter")]
+    ParameterAfterRest { name: String, span: Option<Span> },
+
+    /// `span` locates the name of the parameter, if the parser was given spans.
+    #[error("parameter {name} has no default value but follows one that has")]
+    RequiredAfterDefault { name: String, span: Option<Span> },
+
+    /// `span` locates the second occurrence of the name, if the parser was given spans.
+    #[error("duplicate parameter {name}")]
+    DuplicateParameter { name: String, span: Option<Span> },
+
+    /// `span` locates the argument, if the parser was given spans.
+    #[error("positional argument {argument:?} follows named arguments")]
+    PositionalAfterNamed { argument: Box<Expression>, span: Option<Span> },
+
+    /// `span` locates the second argument of that name, if the parser was given spans.
+    #[error("argument {name} is passed more than once")]
+    DuplicateArgument { name: String, span: Option<Span> },
+
+    /// A `break` or `continue` statement is outside of any loop in its function.
+    /// `span` locates `token`, if the parser was given spans.
+    #[error("{token} outside of a loop")]
+    OutsideLoop { token: Token, span: Option<Span> },
+
+    /// An import or export statement is inside a block.
+    /// `span` locates `token`, if the parser was given spans.
+    #[error("{token} is only allowed at the top level")]
+    NotTopLevel { token: Token, span: Option<Span> },
+
+    /// A doc comment precedes something other than a binding, which is all
+    /// that can be documented. `span` locates its first line, if the parser was given spans.
+    #[error("doc comment documents no binding")]
+    DanglingDocComment { span: Option<Span> },
+
+    /// Blocks, expressions, patterns or types are nested deeper than the parser's limit.
+    /// `span` locates the token that would exceed it, if the parser was given spans.
+    #[error("nesting deeper than {limit} levels")]
+    TooDeep { limit: usize, span: Option<Span> },
+
+    /// The source continues after the expression that should span it.
+    /// `token` is the first one left over, located by `span` if the parser was given spans.
+    #[error("unexpected {token} after the end of the expression")]
+    TrailingToken { token: Token, span: Option<Span> },
+
+    /// A statement is followed by another token on the same line.
+    /// `span` locates `token`, if the parser was given spans.
+    #[error("expected `;` or a line break before {token}")]
+    MissingSeparator { token: Token, span: Option<Span> },
+
+    /// The source ends in the middle of `context`, if any.
+    #[error("reached the end of the source{}", within(.context))]
+    EOF { context: Option<Context> },
+}
+
+/// Details of an [`Error::ExpectedToken`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Every kind of token the parser would have accepted, in the order it
+    /// tried them. Identifiers, integers and strings hold a placeholder value.
+    pub expected: Vec<Token>,
+    /// The offending token, or `None` at the end of the source.
+    pub found: Option<Token>,
+    /// Where `found` is, if the parser was given spans.
+    pub span: Option<Span>,
+    /// The innermost construct being parsed.
+    pub context: Option<Context>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kinds: Vec<String> = self.expected.iter().map(describe_kind).collect();
+        match kinds.as_slice() {
+            [kind] => write!(f, "expected {}", kind)?,
+            kinds => write!(f, "expected one of {}", kinds.join(", "))?,
+        }
+        match &self.found {
+            Some(tok) => write!(f, " but found `{}`", tok)?,
+            None => write!(f, " but reached the end of the source")?,
+        }
+        write!(f, "{}", within(&self.context))
+    }
+}
+
+/// Names the kind of `tok` as listed among the expected tokens.
+fn describe_kind(tok: &Token) -> String {
+    match tok {
+        Token::Identifier(_) => "an identifier".to_string(),
+        Token::Integer(_) => "an integer".to_string(),
+        Token::String(_) => "a string".to_string(),
+        tok => format!("`{}`", tok),
+    }
+}
+
+fn within(context: &Option<Context>) -> String {
+    match context {
+        Some(context) => format!(" in {}", context.construct),
+        None => String::new(),
+    }
+}
+
+/// A construct being parsed when an error occurs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Context {
+    pub construct: Construct,
+    /// Where the keyword or delimiter opening the construct is,
+    /// if the parser was given spans.
+    pub opening: Option<Span>,
+}
+
+/// The syntactic constructs that errors can refer to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Construct {
+    Block,
+    Let,
+    While,
+    For,
+    Import,
+    Parentheses,
+    If,
+    Match,
+    Array,
+    Hash,
+    Arguments,
+    Index,
+    FunctionParameters,
+    MacroParameters,
+    ArrayPattern,
+    HashPattern,
+    Type,
+}
+
+impl fmt::Display for Construct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Construct::Block => "block",
+            Construct::Let => "let statement",
+            Construct::While => "while loop",
+            Construct::For => "for loop",
+            Construct::Import => "import statement",
+            Construct::Parentheses => "parentheses",
+            Construct::If => "if expression",
+            Construct::Match => "match expression",
+            Construct::Array => "array literal",
+            Construct::Hash => "hash literal",
+            Construct::Arguments => "call arguments",
+            Construct::Index => "index expression",
+            Construct::FunctionParameters => "function parameters",
+            Construct::MacroParameters => "macro parameters",
+            Construct::ArrayPattern => "array pattern",
+            Construct::HashPattern => "hash pattern",
+            Construct::Type => "type",
+        };
+        write!(f, "{}", name)
+    }
 }
@@ -1,83 +1,529 @@
+mod arena;
+pub use arena::*;
+
 mod ast;
 pub use ast::*;
 
+mod build;
+pub use build::*;
+
 mod error;
 pub use error::*;
 
+mod printer;
+
+mod sexp;
+pub use sexp::*;
+
+pub mod visit;
+
 use std::iter::Peekable;
 use std::mem;
+use std::ops::Range;
+
+use crate::cst::NodeKind;
+use crate::lexer::{Span, Token};
+
+/// Default maximum nesting of blocks, expressions, patterns and types.
+/// It keeps the parser's recursion well within the stack of a thread.
+pub const DEFAULT_DEPTH_LIMIT: usize = 128;
+
+/// Number of operators in a chain like `a + b + c` that count as one level
+/// of nesting. A chain is parsed in a loop, but nests its left operands as
+/// deeply as it is long, and the tree is still dropped and walked recursively.
+const CHAIN_LINKS_PER_LEVEL: usize = 64;
 
-use crate::lexer::Token;
+/// Tokens that can begin an expression.
+const EXPRESSION_START: &[Token] = &[
+    Token::Identifier(String::new()),
+    Token::Integer(0),
+    Token::String(String::new()),
+    Token::True,
+    Token::False,
+    Token::Bang,
+    Token::Minus,
+    Token::LeftParen,
+    Token::LeftBracket,
+    Token::LeftBrace,
+    Token::If,
+    Token::Match,
+    Token::Function,
+    Token::Bar,
+    Token::Or,
+    Token::Macro,
+];
 
-pub struct Parser<'a, T: Iterator<Item = &'a Token>> {
+/// Keywords that begin a statement other than an expression statement.
+const STATEMENT_START: &[Token] = &[
+    Token::Let,
+    Token::Return,
+    Token::While,
+    Token::For,
+    Token::Break,
+    Token::Continue,
+    Token::Import,
+    Token::Export,
+];
+
+/// Tokens that can begin a pattern.
+const PATTERN_START: &[Token] = &[
+    Token::Identifier(String::new()),
+    Token::Integer(0),
+    Token::String(String::new()),
+    Token::True,
+    Token::False,
+    Token::Minus,
+    Token::LeftBracket,
+    Token::LeftBrace,
+];
+
+/// Tokens that can be the key of a hash pattern entry.
+const HASH_PATTERN_KEYS: &[Token] = &[
+    Token::Identifier(String::new()),
+    Token::Integer(0),
+    Token::String(String::new()),
+    Token::True,
+    Token::False,
+];
+
+/// Tokens that can begin a type.
+const TYPE_START: &[Token] = &[
+    Token::Identifier(String::new()),
+    Token::LeftBracket,
+    Token::LeftBrace,
+    Token::Function,
+];
+
+/// The parameters of a function literal, and its rest parameter.
+type Parameters<E> = (Vec<ParamNode<E>>, Option<String>);
+
+/// Parses a sequence of tokens into an [`AST`], or into an [`ArenaAST`].
+/// The [`Builder`] `B` makes the nodes of the tree as they are parsed.
+///
+/// # Statement separators
+///
+/// Every statement ends at the first of:
+///
+/// - a `;`, which is part of the statement;
+/// - the `}` closing the enclosing block, or the end of the source;
+/// - a line break, that is, the next token starts on a later line than the one
+///   the last token of the statement ends on, as a string may span lines.
+///
+/// Statements ending with a block, namely loops, function declarations and
+/// `if` or `match` expressions, need no separator, although a `;` is allowed.
+/// Function, lambda, macro and hash literals also end with `}`, but they are
+/// values rather than blocks run for their effect, so an expression statement
+/// ending with one needs a separator, as does a `let` statement binding it.
+///
+/// Anything else after a statement, such as a second expression on the same
+/// line, is an [`Error::MissingSeparator`].
+///
+/// A line break also ends an expression, unless the next line starts with
+/// an infix operator, like `+` or `|>`, which continues it. Of the operators
+/// that can also begin an expression, `(`, `[` and `-` begin a new statement
+/// instead of calling, indexing or subtracting from the one above, while
+/// `||` continues with a logical or rather than beginning a lambda without
+/// parameters, which would be of no use as a statement of its own.
+///
+/// Line breaks are only known if the span of each token is provided with
+/// [`Parser::with_spans`]. Otherwise, all the tokens are on the same line
+/// and statements must be separated by semicolons.
+///
+/// # Errors
+///
+/// An [`Error::ExpectedToken`] lists every token that could have come
+/// in place of the offending one, and names the innermost construct
+/// being parsed. With spans, it also locates both the offending token
+/// and the token opening that construct.
+pub struct Parser<'a, T: Iterator<Item = &'a Token>, B: Builder = Arena> {
     tokens: Peekable<T>,
+    /// Location of each token, if known.
+    spans: Vec<Span>,
+    /// Tokens tried at the current position, listed by [`Error::ExpectedToken`].
+    expected: Vec<Token>,
+    /// Constructs enclosing the current position, the innermost last.
+    contexts: Vec<Context>,
+    /// Makes the nodes parsed so far.
+    builder: B,
+    /// Index of the next token.
+    position: usize,
+    /// Number of loops enclosing the current statement, within the
+    /// innermost function literal.
+    loop_depth: usize,
+    /// Number of blocks, expressions, patterns and types being parsed
+    /// that enclose the current one, counting the operands of operators.
+    depth: usize,
+    depth_limit: usize,
+    /// Kind and token range of each construct parsed so far, innermost
+    /// first, if recorded for a concrete syntax tree.
+    syntax: Option<Vec<(NodeKind, Range<usize>)>>,
 }
 
-impl<'a, T: Iterator<Item = &'a Token>> Parser<'a, T> {
+impl<'a, T: Iterator<Item = &'a Token>, B: Builder> Parser<'a, T, B> {
     pub fn new(it: T) -> Self {
-        Self { tokens: it.peekable() }
+        Self {
+            tokens: it.peekable(),
+            spans: Vec::new(),
+            expected: Vec::new(),
+            contexts: Vec::new(),
+            builder: B::default(),
+            position: 0,
+            loop_depth: 0,
+            depth: 0,
+            depth_limit: DEFAULT_DEPTH_LIMIT,
+            syntax: None,
+        }
     }
 
-    pub fn parse(&mut self) -> Result<AST, Error> {
-        let statements = self.parse_statements().map_err(|partials| partials.error)?;
-        Ok(AST::new(statements))
+    /// Sets how deeply blocks, expressions, patterns and types can be nested
+    /// before parsing fails with [`Error::TooDeep`]. Every level uses
+    /// some native stack, so untrusted sources need a limit. Operators nest
+    /// their left operand, but a chain like `a + b + c` is parsed without
+    /// recursion, so it only counts one level every 64 operators.
+    pub fn with_depth_limit(mut self, limit: usize) -> Self {
+        self.depth_limit = limit;
+        self
     }
 
-    fn parse_statements(&mut self) -> Result<Vec<Statement>, PartialStatements> {
+    /// Sets the span of each token, in the order of the tokens, so that
+    /// line breaks can separate statements and errors can tell where they are.
+    pub fn with_spans(mut self, spans: impl IntoIterator<Item = Span>) -> Self {
+        self.spans = spans.into_iter().collect();
+        self
+    }
+
+    /// Records the kind and token range of every construct parsed,
+    /// which [`crate::cst::parse`] builds the syntax tree from.
+    pub(crate) fn with_syntax(mut self) -> Self {
+        self.syntax = Some(Vec::new());
+        self
+    }
+
+    /// Returns the constructs recorded so far, see [`Parser::with_syntax`].
+    pub(crate) fn take_syntax(&mut self) -> Vec<(NodeKind, Range<usize>)> {
+        self.syntax.take().unwrap_or_default()
+    }
+
+    /// Parses statements up to the end of the source.
+    fn parse_program(&mut self) -> Result<Vec<B::Stmt>, Error> {
+        let statements = self.parse_statements(true)?;
+        // parse_statements stops at a closing brace, which is only
+        // legitimate at the end of a block.
+        if self.tokens.peek().is_some() {
+            for token in STATEMENT_START.iter().chain(EXPRESSION_START) {
+                self.check(token.clone());
+            }
+            return Err(self.mismatch());
+        }
+        Ok(statements)
+    }
+
+    /// Parses statements until the end of the source or of the enclosing block.
+    /// Imports and exports are only allowed at the top level of the source.
+    fn parse_statements(&mut self, top_level: bool) -> Result<Vec<B::Stmt>, Error> {
         let mut statements = Vec::new();
-        while let Some(tok) = self.tokens.peek() {
-            let statement = match tok {
-                Token::Let => self.parse_let(),
-                Token::Return => self.parse_return(),
-                _ => self.parse_expression_statement(),
+        loop {
+            let start = self.position;
+            let doc = match self.tokens.peek() {
+                Some(Token::DocComment(_)) => Some(self.parse_doc_comment()?),
+                _ => None,
             };
-            match statement {
-                Ok(stat) => statements.push(stat),
-                Err(partial) => {
-                    return Err(PartialStatements {
-                        statements,
-                        error: partial,
+            let next = self.tokens.peek().copied();
+            // Only bindings keep their documentation.
+            if doc.is_some() && !matches!(next, Some(Token::Let | Token::Function | Token::Export)) {
+                return Err(Error::DanglingDocComment {
+                    span: self.spans.get(start).copied(),
+                });
+            }
+            let Some(keyword) = next else {
+                break;
+            };
+            let statement = match keyword {
+                Token::Import | Token::Export if !top_level => {
+                    return Err(Error::NotTopLevel {
+                        token: keyword.clone(),
+                        span: self.next_span(),
                     })
                 }
-            }
+                Token::RightBrace => break,
+                Token::Let => self.parse_let(doc),
+                Token::Function => self.parse_function_statement(doc),
+                Token::Export => self.parse_export(doc),
+                Token::Return => self.parse_return(),
+                Token::While => self.parse_while(),
+                Token::For => self.parse_for(),
+                Token::Break | Token::Continue => self.parse_loop_control(),
+                Token::Import => self.parse_import(),
+                _ => self.parse_expression_statement(),
+            }?;
+            self.mark(statement_kind(&statement, keyword), start);
+            statements.push(self.builder.statement(statement));
         }
         Ok(statements)
     }
 
-    fn parse_let(&mut self) -> Result<Statement, Error> {
+    /// Parses a list of statements enclosed in braces.
+    fn parse_block(&mut self) -> Result<Vec<B::Stmt>, Error> {
+        let start = self.position;
+        self.expect_token(Token::LeftBrace)?;
+        let statements = self.within(Construct::Block, |parser| {
+            let statements = parser.nested(|parser| parser.parse_statements(false))?;
+            parser.expect_token(Token::RightBrace)?;
+            Ok(statements)
+        })?;
+        self.mark(NodeKind::Block, start);
+        Ok(statements)
+    }
+
+    /// Parses consecutive doc comment lines into a single text.
+    fn parse_doc_comment(&mut self) -> Result<String, Error> {
+        let mut lines = Vec::new();
+        while let Some(Token::DocComment(line)) = self.tokens.peek() {
+            lines.push(line.as_str());
+            self.next_token()?;
+        }
+        Ok(lines.join("\n"))
+    }
+
+    fn parse_let(&mut self, doc: Option<String>) -> Result<StmtNode<B::Expr, B::Stmt>, Error> {
         self.expect_token(Token::Let)?;
-        let identifier = self
-            .expect_token(Token::Identifier(String::default()))
-            .map(|tok| match tok {
-                Token::Identifier(s) => s.to_string(),
-                _ => unreachable!(),
-            })?;
-        self.expect_token(Token::Assign)?;
-        while self.next_token()? != &Token::Semicolon {}
-        Ok(Statement::Let {
-            identifier,
-            expression: Expression::None,
+        self.within(Construct::Let, |parser| {
+            let start = parser.position;
+            let pattern = parser.parse_pattern()?;
+            if pattern.has_literal() {
+                let span = parser.span_of(start..parser.position);
+                return Err(Error::LiteralPattern {
+                    pattern: Box::new(pattern),
+                    span,
+                });
+            }
+            let annotation = if parser.eat(Token::Colon)? {
+                Some(parser.parse_type()?)
+            } else {
+                None
+            };
+            parser.expect_token(Token::Assign)?;
+            let expression = parser.parse_expression_at(Priority::Lowest)?;
+            parser.end_statement()?;
+            Ok(StmtNode::Let {
+                pattern,
+                annotation,
+                expression,
+                doc,
+            })
         })
     }
 
-    fn parse_return(&mut self) -> Result<Statement, Error> {
+    fn parse_return(&mut self) -> Result<StmtNode<B::Expr, B::Stmt>, Error> {
         self.expect_token(Token::Return)?;
-        while self.next_token()? != &Token::Semicolon {}
-        Ok(Statement::Return(Expression::None))
+        // A return without a value returns nothing.
+        let expression = match self.tokens.peek() {
+            None | Some(Token::Semicolon | Token::RightBrace) => self.builder.expression(ExprNode::None),
+            Some(_) => self.parse_expression_at(Priority::Lowest)?,
+        };
+        self.end_statement()?;
+        Ok(StmtNode::Return(expression))
+    }
+
+    fn parse_while(&mut self) -> Result<StmtNode<B::Expr, B::Stmt>, Error> {
+        self.expect_token(Token::While)?;
+        self.within(Construct::While, |parser| {
+            parser.expect_token(Token::LeftParen)?;
+            let cond = parser.parse_expression_at(Priority::Lowest)?;
+            parser.expect_token(Token::RightParen)?;
+            let body = parser.parse_loop_body()?;
+            Ok(StmtNode::While { cond, body })
+        })
+    }
+
+    fn parse_for(&mut self) -> Result<StmtNode<B::Expr, B::Stmt>, Error> {
+        self.expect_token(Token::For)?;
+        self.within(Construct::For, |parser| {
+            parser.expect_token(Token::LeftParen)?;
+            let variable = parser.expect_identifier()?;
+            parser.expect_token(Token::In)?;
+            let iterable = parser.parse_expression_at(Priority::Lowest)?;
+            parser.expect_token(Token::RightParen)?;
+            let body = parser.parse_loop_body()?;
+            Ok(StmtNode::For {
+                variable,
+                iterable,
+                body,
+            })
+        })
+    }
+
+    fn parse_loop_body(&mut self) -> Result<Vec<B::Stmt>, Error> {
+        self.loop_depth += 1;
+        let body = self.parse_block();
+        self.loop_depth -= 1;
+        let body = body?;
+        self.end_block_statement()?;
+        Ok(body)
+    }
+
+    /// Parses a `break` or `continue` statement, which must be inside a loop.
+    fn parse_loop_control(&mut self) -> Result<StmtNode<B::Expr, B::Stmt>, Error> {
+        let tok = self.next_token()?.clone();
+        if self.loop_depth == 0 {
+            let span = self.span_of(self.position - 1..self.position);
+            return Err(Error::OutsideLoop { token: tok, span });
+        }
+        self.end_statement()?;
+        Ok(if tok == Token::Break {
+            StmtNode::Break
+        } else {
+            StmtNode::Continue
+        })
+    }
+
+    fn parse_import(&mut self) -> Result<StmtNode<B::Expr, B::Stmt>, Error> {
+        self.expect_token(Token::Import)?;
+        self.within(Construct::Import, |parser| {
+            let path = match parser.expect_token(Token::String(String::default()))? {
+                Token::String(s) => s.to_string(),
+                _ => unreachable!(),
+            };
+            parser.expect_token(Token::As)?;
+            let alias = parser.expect_identifier()?;
+            parser.end_statement()?;
+            Ok(StmtNode::Import { path, alias })
+        })
+    }
+
+    fn parse_export(&mut self, doc: Option<String>) -> Result<StmtNode<B::Expr, B::Stmt>, Error> {
+        self.expect_token(Token::Export)?;
+        let start = self.position;
+        let (statement, kind) = if self.eat(Token::Function)? {
+            (self.parse_function_declaration(doc)?, NodeKind::FunctionDeclaration)
+        } else {
+            (self.parse_let(doc)?, NodeKind::LetStatement)
+        };
+        if let StmtNode::Let { pattern, .. } = &statement {
+            if !matches!(pattern, Pattern::Identifier(_)) {
+                return Err(Error::ExportPattern {
+                    pattern: Box::new(pattern.clone()),
+                    span: self.span_of(start..self.position),
+                });
+            }
+        }
+        self.mark(kind, start);
+        Ok(StmtNode::Export(self.builder.statement(statement)))
+    }
+
+    /// Parses a statement starting with `fn`, which declares a function
+    /// if a name follows. Otherwise, the function literal begins an expression.
+    fn parse_function_statement(&mut self, doc: Option<String>) -> Result<StmtNode<B::Expr, B::Stmt>, Error> {
+        let start = self.position;
+        self.expect_token(Token::Function)?;
+        if let Some(Token::Identifier(_)) = self.tokens.peek() {
+            return self.parse_function_declaration(doc);
+        }
+        let exp = self.nested(|parser| {
+            let function = parser.parse_function(None)?;
+            let function = parser.builder.expression(function);
+            parser.mark(NodeKind::FunctionExpr, start);
+            parser.parse_infixes(function, Priority::Lowest, start)
+        })?;
+        self.end_expression_statement(exp)
+    }
+
+    /// Parses the rest of `fn name(parameters) { body }`, which binds
+    /// the function to its name like a `let` statement.
+    fn parse_function_declaration(&mut self, doc: Option<String>) -> Result<StmtNode<B::Expr, B::Stmt>, Error> {
+        let name = self.expect_identifier()?;
+        let function = self.nested(|parser| parser.parse_function(Some(name.clone())))?;
+        let expression = self.builder.expression(function);
+        self.end_block_statement()?;
+        Ok(StmtNode::Let {
+            pattern: Pattern::Identifier(name),
+            annotation: None,
+            expression,
+            doc,
+        })
+    }
+
+    fn parse_expression_statement(&mut self) -> Result<StmtNode<B::Expr, B::Stmt>, Error> {
+        let exp = self.parse_expression_at(Priority::Lowest)?;
+        self.end_expression_statement(exp)
+    }
+
+    /// Consumes the separator after the expression statement `exp`.
+    fn end_expression_statement(&mut self, exp: B::Expr) -> Result<StmtNode<B::Expr, B::Stmt>, Error> {
+        if self.builder.ends_with_brace(&exp) {
+            self.end_block_statement()?;
+        } else {
+            self.end_statement()?;
+        }
+        Ok(StmtNode::Expression(exp))
     }
 
-    fn parse_expression_statement(&mut self) -> Result<Statement, Error> {
-        let exp = self.parse_expression(Priority::Lowest)?;
-        let _ = self.expect_token(Token::Semicolon); // Semicolon is optional.
-        Ok(Statement::Expression(exp))
+    /// Consumes the optional semicolon after a statement ending with a block.
+    fn end_block_statement(&mut self) -> Result<(), Error> {
+        self.eat(Token::Semicolon)?;
+        Ok(())
+    }
+
+    /// Consumes the separator after a statement, as described in [`Parser`].
+    fn end_statement(&mut self) -> Result<(), Error> {
+        match self.tokens.peek().copied() {
+            Some(Token::Semicolon) => {
+                self.next_token()?;
+                Ok(())
+            }
+            None | Some(Token::RightBrace) => Ok(()),
+            Some(_) if self.at_line_break() => Ok(()),
+            Some(tok) => Err(Error::MissingSeparator {
+                token: tok.clone(),
+                span: self.next_span(),
+            }),
+        }
+    }
+
+    /// Tells whether the next token starts on a later line than the one the last token read ends on.
+    fn at_line_break(&self) -> bool {
+        let Some(last) = self.position.checked_sub(1) else {
+            return false;
+        };
+        match (self.spans.get(last), self.spans.get(self.position)) {
+            (Some(last), Some(next)) => next.start.line > last.end.line,
+            _ => false,
+        }
     }
 
     /// Parses an expression using the Pratt parsing algorithm.
-    fn parse_expression(&mut self, priority: Priority) -> Result<Expression, Error> {
-        let mut exp = self.parse_prefix()?;
-        while let Some(tok) = self.tokens.peek() {
-            if tok != &&Token::Semicolon && priority < Priority::from_token(tok) {
-                exp = self.parse_infix(exp)?;
+    fn parse_expression_at(&mut self, priority: Priority) -> Result<B::Expr, Error> {
+        self.nested(|parser| {
+            let start = parser.position;
+            let exp = parser.parse_prefix()?;
+            parser.parse_infixes(exp, priority, start)
+        })
+    }
+
+    /// Parses the infix operators following `exp`, which begins with the token
+    /// at `start`, that bind tighter than `priority`.
+    fn parse_infixes(&mut self, exp: B::Expr, priority: Priority, start: usize) -> Result<B::Expr, Error> {
+        // Each operator nests the expression parsed so far one level deeper,
+        // so a long chain like `a + b + c + ...` is as deep as it is long.
+        // Only the right operands recurse, so the links count for less.
+        let depth = self.depth;
+        let result = self.parse_infix_chain(exp, priority, start);
+        self.depth = depth;
+        result
+    }
+
+    fn parse_infix_chain(&mut self, mut exp: B::Expr, priority: Priority, start: usize) -> Result<B::Expr, Error> {
+        let mut links = 0;
+        while let Some(tok) = self.tokens.peek().copied() {
+            // These tokens may also start the next statement.
+            let starts = matches!(tok, Token::LeftParen | Token::LeftBracket | Token::Minus);
+            let continues = !(starts && self.at_line_break());
+            if tok != &Token::Semicolon && continues && priority < Priority::from_token(tok) {
+                if links % CHAIN_LINKS_PER_LEVEL == 0 {
+                    self.descend()?;
+                }
+                links += 1;
+                exp = self.parse_infix(exp, start)?;
             } else {
                 break;
             }
@@ -85,59 +531,112 @@ impl<'a, T: Iterator<Item = &'a Token>> Parser<'a, T> {
         Ok(exp)
     }
 
-    fn parse_prefix(&mut self) -> Result<Expression, Error> {
-        let tok = self.next_token()?;
-        match tok {
-            Token::True | Token::False => Ok(Expression::Boolean(tok == &Token::True)),
-            Token::Identifier(s) => Ok(Expression::Identifier(s.to_string())),
-            Token::Integer(int) => Ok(Expression::Integer(*int)),
+    /// Runs `parse` one nesting level deeper, failing if that exceeds the limit.
+    fn nested<R>(&mut self, parse: impl FnOnce(&mut Self) -> Result<R, Error>) -> Result<R, Error> {
+        self.descend()?;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Goes one nesting level deeper, failing if that exceeds the limit.
+    fn descend(&mut self) -> Result<(), Error> {
+        if self.depth == self.depth_limit {
+            return Err(Error::TooDeep {
+                limit: self.depth_limit,
+                span: self.next_span(),
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Runs `parse` within `construct`, whose opening token was the last one read.
+    fn within<R>(&mut self, construct: Construct, parse: impl FnOnce(&mut Self) -> Result<R, Error>) -> Result<R, Error> {
+        let opening = self.position.checked_sub(1).and_then(|last| self.spans.get(last)).copied();
+        self.contexts.push(Context { construct, opening });
+        let result = parse(self);
+        self.contexts.pop();
+        result
+    }
+
+    /// Records that the tokens from `start` up to the current position make
+    /// up a construct of the given kind, if building a syntax tree.
+    fn mark(&mut self, kind: NodeKind, start: usize) {
+        if let Some(syntax) = &mut self.syntax {
+            if start < self.position {
+                syntax.push((kind, start..self.position));
+            }
+        }
+    }
+
+    fn parse_prefix(&mut self) -> Result<B::Expr, Error> {
+        let start = self.position;
+        let tok = self.expect_one_of(EXPRESSION_START)?;
+        let exp = match tok {
+            Token::True | Token::False => ExprNode::Boolean(tok == &Token::True),
+            Token::Identifier(s) => ExprNode::Identifier(s.to_string()),
+            Token::Integer(int) => ExprNode::Integer(*int),
+            Token::String(s) => ExprNode::String(s.to_string()),
             Token::Bang | Token::Minus => {
-                let tok = tok.clone();
-                let expr = self.parse_expression(Priority::Unary)?;
-                Ok(Expression::Unary {
-                    operator: tok,
-                    expression: Box::new(expr),
-                })
+                let expr = self.parse_expression_at(Priority::Unary)?;
+                ExprNode::Unary {
+                    operator: tok.clone(),
+                    expression: expr,
+                }
             }
+            Token::LeftBracket => ExprNode::Array(
+                self.within(Construct::Array, |parser| parser.parse_expression_list(Token::RightBracket))?,
+            ),
             Token::LeftParen => {
                 // Reset the priority as if we were parsing an expression
                 // from the beginning.
-                let expr = self.parse_expression(Priority::Lowest)?;
-                self.expect_token(Token::RightParen)?;
-                Ok(expr)
-            }
-            Token::If => {
-                self.expect_token(Token::LeftParen)?;
-                let cond = self.parse_expression(Priority::Lowest)?;
-                self.expect_token(Token::RightParen)?;
-                self.expect_token(Token::LeftBrace)?;
-                let conseq: Vec<Statement> = match self.parse_statements() {
-                    Ok(stats) => stats,
-                    Err(e) => {
-                        if let Error::ExpectedToken { expected: _, got } = &e.error {
-                            if got == &Token::RightBrace {
-                                e.statements
-                            } else {
-                                return Err(e.error);
-                            }
-                        } else {
-                            return Err(e.error);
-                        }
-                    }
+                let expr = self.within(Construct::Parentheses, |parser| {
+                    let expr = parser.parse_expression_at(Priority::Lowest)?;
+                    parser.expect_token(Token::RightParen)?;
+                    Ok(expr)
+                })?;
+                self.mark(NodeKind::ParenExpr, start);
+                return Ok(expr);
+            }
+            // A brace in prefix position always opens a hash literal:
+            // blocks are only parsed where the grammar requires them,
+            // e.g. after `if`, `else` and `fn`.
+            Token::LeftBrace => self.within(Construct::Hash, Self::parse_hash)?,
+            Token::If => self.within(Construct::If, |parser| {
+                parser.expect_token(Token::LeftParen)?;
+                let cond = parser.parse_expression_at(Priority::Lowest)?;
+                parser.expect_token(Token::RightParen)?;
+                let conseq = parser.parse_block()?;
+                let altern = if parser.eat(Token::Else)? {
+                    Some(parser.parse_block()?)
+                } else {
+                    None
                 };
-                Ok(Expression::If {
-                    cond: Box::new(cond),
-                    conseq,
-                    altern: None,
-                })
+                Ok(ExprNode::If { cond, conseq, altern })
+            })?,
+            Token::Match => self.within(Construct::Match, Self::parse_match)?,
+            Token::Function => self.parse_function(None)?,
+            Token::Bar => self.parse_lambda(Token::Bar)?,
+            // In place of an operand, `||` is the empty parameter list of a lambda.
+            Token::Or => self.parse_lambda(Token::Or)?,
+            Token::Macro => {
+                let parameters = self.parse_parameter_names()?;
+                let body = self.parse_function_body()?;
+                ExprNode::Macro { parameters, body }
             }
             _ => unreachable!(),
-        }
+        };
+        let kind = expression_kind(tok, &exp);
+        let exp = self.builder.expression(exp);
+        self.mark(kind, start);
+        Ok(exp)
     }
 
-    fn parse_infix(&mut self, left_expr: Expression) -> Result<Expression, Error> {
+    /// Parses the operator following `left_expr`, which begins with the token at `start`.
+    fn parse_infix(&mut self, left_expr: B::Expr, start: usize) -> Result<B::Expr, Error> {
         let tok = self.next_token()?;
-        match tok {
+        let exp = match tok {
             Token::Plus
             | Token::Minus
             | Token::Asterisk
@@ -146,37 +645,590 @@ impl<'a, T: Iterator<Item = &'a Token>> Parser<'a, T> {
             | Token::LessThan
             | Token::Equal
             | Token::NotEqual => {
-                let tok = tok.clone();
-                let right_exp = self.parse_expression(Priority::from_token(&tok))?;
-                Ok(Expression::Binary {
-                    left: Box::new(left_expr),
-                    operator: tok,
-                    right: Box::new(right_exp),
+                let right_exp = self.parse_expression_at(Priority::from_token(tok))?;
+                ExprNode::Binary {
+                    left: left_expr,
+                    operator: tok.clone(),
+                    right: right_exp,
+                }
+            }
+            Token::And | Token::Or => {
+                let right_exp = self.parse_expression_at(Priority::from_token(tok))?;
+                ExprNode::Logical {
+                    left: left_expr,
+                    operator: tok.clone(),
+                    right: right_exp,
+                }
+            }
+            Token::Assign | Token::PlusAssign | Token::MinusAssign | Token::AsteriskAssign | Token::SlashAssign => {
+                let operator = match tok {
+                    Token::PlusAssign => Some(Token::Plus),
+                    Token::MinusAssign => Some(Token::Minus),
+                    Token::AsteriskAssign => Some(Token::Asterisk),
+                    Token::SlashAssign => Some(Token::Slash),
+                    _ => None,
+                };
+                if !self.builder.is_assignable(&left_expr) {
+                    return Err(Error::InvalidAssignmentTarget {
+                        target: Box::new(self.builder.to_expression(&left_expr)),
+                        span: self.span_of(start..self.position - 1),
+                    });
+                }
+                // Assignment is right-associative: `a = b = c` is `a = (b = c)`.
+                let value = self.parse_expression_at(Priority::Lowest)?;
+                ExprNode::Assign {
+                    target: left_expr,
+                    operator,
+                    value,
+                }
+            }
+            Token::LeftParen => {
+                let start_of_list = self.position - 1;
+                let mut arguments = Vec::new();
+                let mut named: Vec<(String, B::Expr)> = Vec::new();
+                let list = self.within(Construct::Arguments, |parser| {
+                    parser.parse_list(Token::RightParen, |parser| {
+                        let start = parser.position;
+                        let (name, value) = parser.parse_argument()?;
+                        Ok((name, value, parser.span_of(start..parser.position)))
+                    })
+                })?;
+                self.mark(NodeKind::ArgumentList, start_of_list);
+                for (name, value, span) in list {
+                    match name {
+                        Some(name) if named.iter().any(|(other, _)| *other == name) => {
+                            return Err(Error::DuplicateArgument { name, span })
+                        }
+                        Some(name) => named.push((name, value)),
+                        None if !named.is_empty() => {
+                            return Err(Error::PositionalAfterNamed {
+                                argument: Box::new(self.builder.to_expression(&value)),
+                                span,
+                            })
+                        }
+                        None => arguments.push(value),
+                    }
+                }
+                ExprNode::Call {
+                    function: left_expr,
+                    arguments,
+                    named,
+                }
+            }
+            Token::Pipe => {
+                // `x |> f(y)` is lowered to `f(x, y)`, and `x |> f` to `f(x)`.
+                let mut right_exp = self.parse_expression_at(Priority::Pipe)?;
+                if let Some(arguments) = self.builder.arguments_mut(&mut right_exp) {
+                    arguments.insert(0, left_expr);
+                    self.mark(NodeKind::PipeExpr, start);
+                    return Ok(right_exp);
+                }
+                ExprNode::Call {
+                    function: right_exp,
+                    arguments: vec![left_expr],
+                    named: Vec::new(),
+                }
+            }
+            Token::LeftBracket => {
+                let index = self.within(Construct::Index, |parser| {
+                    let index = parser.parse_expression_at(Priority::Lowest)?;
+                    parser.expect_token(Token::RightBracket)?;
+                    Ok(index)
+                })?;
+                ExprNode::Index { left: left_expr, index }
+            }
+            _ => return Ok(left_expr),
+        };
+        let kind = expression_kind(tok, &exp);
+        let exp = self.builder.expression(exp);
+        self.mark(kind, start);
+        Ok(exp)
+    }
+
+    /// Parses a comma-separated list of expressions, consuming the `end` token
+    /// that closes it. A trailing comma before `end` is allowed.
+    fn parse_expression_list(&mut self, end: Token) -> Result<Vec<B::Expr>, Error> {
+        self.parse_list(end, |parser| parser.parse_expression_at(Priority::Lowest))
+    }
+
+    /// Parses a comma-separated list of items, consuming the `end` token
+    /// that closes it. A trailing comma before `end` is allowed.
+    fn parse_list<I>(
+        &mut self,
+        end: Token,
+        mut parse_item: impl FnMut(&mut Self) -> Result<I, Error>,
+    ) -> Result<Vec<I>, Error> {
+        let mut list = Vec::new();
+        loop {
+            if self.eat(end.clone())? {
+                break;
+            }
+            list.push(parse_item(self)?);
+            if self.eat(end.clone())? {
+                break;
+            }
+            if !self.eat(Token::Comma)? {
+                return Err(self.mismatch());
+            }
+        }
+        Ok(list)
+    }
+
+    /// Parses an argument of a call, along with the parameter
+    /// it is passed to if written `name: value`.
+    fn parse_argument(&mut self) -> Result<(Option<String>, B::Expr), Error> {
+        if !matches!(self.tokens.peek(), Some(Token::Identifier(_))) {
+            return Ok((None, self.parse_expression_at(Priority::Lowest)?));
+        }
+        let start = self.position;
+        let name = self.expect_identifier()?;
+        if self.eat(Token::Colon)? {
+            let value = self.parse_expression_at(Priority::Lowest)?;
+            self.mark(NodeKind::NamedArgument, start);
+            return Ok((Some(name), value));
+        }
+        // The identifier begins a positional argument.
+        let value = self.nested(|parser| {
+            let identifier = parser.builder.expression(ExprNode::Identifier(name));
+            parser.mark(NodeKind::Name, start);
+            parser.parse_infixes(identifier, Priority::Lowest, start)
+        })?;
+        Ok((None, value))
+    }
+
+    /// Parses the key-value pairs of a hash literal, whose opening
+    /// brace has already been consumed.
+    fn parse_hash(&mut self) -> Result<ExprNode<B::Expr, B::Stmt>, Error> {
+        let pairs = self.parse_list(Token::RightBrace, |parser| {
+            let start = parser.position;
+            let key = parser.parse_expression_at(Priority::Lowest)?;
+            parser.expect_token(Token::Colon)?;
+            let value = parser.parse_expression_at(Priority::Lowest)?;
+            parser.mark(NodeKind::HashEntry, start);
+            Ok((key, value))
+        })?;
+        Ok(ExprNode::Hash(pairs))
+    }
+
+    /// Parses the subject and arms of a match expression, whose `match`
+    /// keyword has already been consumed.
+    fn parse_match(&mut self) -> Result<ExprNode<B::Expr, B::Stmt>, Error> {
+        self.expect_token(Token::LeftParen)?;
+        let subject = self.parse_expression_at(Priority::Lowest)?;
+        self.expect_token(Token::RightParen)?;
+        self.expect_token(Token::LeftBrace)?;
+        // An unguarded wildcard or binding matches anything,
+        // so no arm can follow it.
+        let mut exhaustive = false;
+        let arms = self.parse_list(Token::RightBrace, |parser| {
+            let start = parser.position;
+            let pattern = parser.parse_pattern()?;
+            if exhaustive {
+                let span = parser.span_of(start..parser.position);
+                return Err(Error::UnreachableArm {
+                    pattern: Box::new(pattern),
+                    span,
+                });
+            }
+            let guard = if parser.eat(Token::If)? {
+                Some(parser.parse_expression_at(Priority::Lowest)?)
+            } else {
+                None
+            };
+            parser.expect_token(Token::FatArrow)?;
+            let body = parser.parse_expression_at(Priority::Lowest)?;
+            parser.mark(NodeKind::MatchArm, start);
+            exhaustive = guard.is_none() && pattern.is_irrefutable();
+            Ok(ArmNode { pattern, guard, body })
+        })?;
+        Ok(ExprNode::Match { subject, arms })
+    }
+
+    fn parse_pattern(&mut self) -> Result<Pattern, Error> {
+        let start = self.position;
+        let pattern = self.nested(Self::parse_pattern_inner)?;
+        let kind = match pattern {
+            Pattern::Wildcard => NodeKind::WildcardPattern,
+            Pattern::Identifier(_) => NodeKind::IdentifierPattern,
+            Pattern::Boolean(_) | Pattern::Integer(_) | Pattern::String(_) => NodeKind::LiteralPattern,
+            Pattern::Array { .. } => NodeKind::ArrayPattern,
+            Pattern::Hash(_) => NodeKind::HashPattern,
+        };
+        self.mark(kind, start);
+        Ok(pattern)
+    }
+
+    fn parse_pattern_inner(&mut self) -> Result<Pattern, Error> {
+        let tok = self.expect_one_of(PATTERN_START)?;
+        match tok {
+            Token::Identifier(s) if s == "_" => Ok(Pattern::Wildcard),
+            Token::Identifier(s) => Ok(Pattern::Identifier(s.to_string())),
+            Token::True | Token::False => Ok(Pattern::Boolean(tok == &Token::True)),
+            Token::Integer(int) => Ok(Pattern::Integer(*int)),
+            Token::Minus => match self.expect_token(Token::Integer(0))? {
+                Token::Integer(int) => Ok(Pattern::Integer(-int)),
+                _ => unreachable!(),
+            },
+            Token::String(s) => Ok(Pattern::String(s.to_string())),
+            Token::LeftBracket => {
+                let mut rest = None;
+                let elements = self.within(Construct::ArrayPattern, |parser| {
+                    parser.parse_list(Token::RightBracket, |parser| {
+                        // A rest pattern must be the last element, so only
+                        // the closing bracket, just tried, can follow it.
+                        if rest.is_some() {
+                            return Err(parser.mismatch());
+                        }
+                        let start = parser.position;
+                        if parser.eat(Token::Ellipsis)? {
+                            rest = Some(parser.expect_identifier()?);
+                            parser.mark(NodeKind::RestPattern, start);
+                            return Ok(None);
+                        }
+                        parser.parse_pattern().map(Some)
+                    })
+                })?;
+                Ok(Pattern::Array {
+                    elements: elements.into_iter().flatten().collect(),
+                    rest,
                 })
             }
-            _ => Ok(left_expr),
+            Token::LeftBrace => {
+                let pairs = self.within(Construct::HashPattern, |parser| {
+                    parser.parse_list(Token::RightBrace, |parser| {
+                        let start = parser.position;
+                        let key = match parser.expect_one_of(HASH_PATTERN_KEYS)? {
+                            tok @ (Token::True | Token::False) => Expression::Boolean(tok == &Token::True),
+                            Token::Integer(int) => Expression::Integer(*int),
+                            Token::String(s) => Expression::String(s.to_string()),
+                            // `{name}` is a shorthand for `{"name": name}`.
+                            Token::Identifier(s) => {
+                                parser.mark(NodeKind::HashPatternEntry, start);
+                                return Ok((Expression::String(s.to_string()), Pattern::Identifier(s.to_string())));
+                            }
+                            _ => unreachable!(),
+                        };
+                        parser.expect_token(Token::Colon)?;
+                        let pattern = parser.parse_pattern()?;
+                        parser.mark(NodeKind::HashPatternEntry, start);
+                        Ok((key, pattern))
+                    })
+                })?;
+                Ok(Pattern::Hash(pairs))
+            }
+            _ => unreachable!(),
         }
     }
 
-    fn next_token(&mut self) -> Result<&Token, Error> {
-        self.tokens.next().ok_or(Error::EOF)
+    /// Parses a function literal, after `fn` and its name if declared with one.
+    fn parse_function(&mut self, name: Option<String>) -> Result<ExprNode<B::Expr, B::Stmt>, Error> {
+        let start = self.position;
+        self.expect_token(Token::LeftParen)?;
+        let (parameters, rest) =
+            self.within(Construct::FunctionParameters, |parser| parser.parse_parameters(Token::RightParen))?;
+        self.mark(NodeKind::ParameterList, start);
+        let ret = if self.eat(Token::Arrow)? {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        let body = self.parse_function_body()?;
+        Ok(ExprNode::Function {
+            name,
+            parameters,
+            rest,
+            ret,
+            body,
+        })
     }
 
-    fn expect_token(&mut self, token: Token) -> Result<&Token, Error> {
-        let tok = self.next_token()?;
-        if mem::discriminant(tok) != mem::discriminant(&token) {
-            return Err(Error::ExpectedToken {
-                expected: token,
-                got: (*tok).clone(),
+    /// Parses a short lambda like `|x| x * 2`, which is sugar for a function
+    /// literal returning the expression. A body in braces is a block,
+    /// as in `|a, b| { a + b }`. `opening` is the token that began it,
+    /// and is `||` if the lambda has no parameters.
+    fn parse_lambda(&mut self, opening: Token) -> Result<ExprNode<B::Expr, B::Stmt>, Error> {
+        let start = self.position - 1;
+        let (parameters, rest) = match opening {
+            Token::Or => (Vec::new(), None),
+            _ => self.within(Construct::FunctionParameters, |parser| parser.parse_parameters(Token::Bar))?,
+        };
+        self.mark(NodeKind::ParameterList, start);
+        let body = if self.check(Token::LeftBrace) {
+            self.parse_function_body()?
+        } else {
+            let loop_depth = mem::take(&mut self.loop_depth);
+            let exp = self.parse_expression_at(Priority::Lowest);
+            self.loop_depth = loop_depth;
+            vec![self.builder.statement(StmtNode::Expression(exp?))]
+        };
+        Ok(ExprNode::Function {
+            name: None,
+            parameters,
+            rest,
+            ret: None,
+            body,
+        })
+    }
+
+    fn parse_function_body(&mut self) -> Result<Vec<B::Stmt>, Error> {
+        // Loops outside the function cannot be broken from inside it.
+        let loop_depth = mem::take(&mut self.loop_depth);
+        let body = self.parse_block();
+        self.loop_depth = loop_depth;
+        body
+    }
+
+    /// Parses the parameters of a function literal up to `end`, along with
+    /// the rest parameter, if any. Parameters with a default value must follow
+    /// the required ones, and the rest parameter must be the last.
+    fn parse_parameters(&mut self, end: Token) -> Result<Parameters<B::Expr>, Error> {
+        let mut parameters: Vec<ParamNode<B::Expr>> = Vec::new();
+        let mut rest = None;
+        self.parse_list(end, |parser| {
+            let start = parser.position;
+            let is_rest = parser.eat(Token::Ellipsis)?;
+            let name = parser.expect_identifier()?;
+            let span = parser.span_of(parser.position - 1..parser.position);
+            if rest.is_some() {
+                return Err(Error::ParameterAfterRest { name, span });
+            }
+            if parameters.iter().any(|param| param.name == name) {
+                return Err(Error::DuplicateParameter { name, span });
+            }
+            if is_rest {
+                rest = Some(name);
+                parser.mark(NodeKind::RestParameter, start);
+                return Ok(());
+            }
+            let annotation = if parser.eat(Token::Colon)? {
+                Some(parser.parse_type()?)
+            } else {
+                None
+            };
+            let default = if parser.eat(Token::Assign)? {
+                Some(parser.parse_expression_at(Priority::Lowest)?)
+            } else if parameters.iter().any(|param| param.default.is_some()) {
+                return Err(Error::RequiredAfterDefault { name, span });
+            } else {
+                None
+            };
+            parameters.push(ParamNode {
+                name,
+                annotation,
+                default,
             });
+            parser.mark(NodeKind::Parameter, start);
+            Ok(())
+        })?;
+        Ok((parameters, rest))
+    }
+
+    /// Parses the parenthesized parameter names of a macro literal.
+    fn parse_parameter_names(&mut self) -> Result<Vec<String>, Error> {
+        let start = self.position;
+        self.expect_token(Token::LeftParen)?;
+        let names = self.within(Construct::MacroParameters, |parser| {
+            parser.parse_list(Token::RightParen, |parser| {
+                let name = parser.expect_identifier()?;
+                parser.mark(NodeKind::Parameter, parser.position - 1);
+                Ok(name)
+            })
+        })?;
+        self.mark(NodeKind::ParameterList, start);
+        Ok(names)
+    }
+
+    fn parse_type(&mut self) -> Result<Type, Error> {
+        let start = self.position;
+        let t = self.nested(Self::parse_type_inner)?;
+        let kind = match t {
+            Type::Named(_) => NodeKind::NamedType,
+            Type::Array(_) => NodeKind::ArrayType,
+            Type::Hash(..) => NodeKind::HashType,
+            Type::Function { .. } => NodeKind::FunctionType,
+        };
+        self.mark(kind, start);
+        Ok(t)
+    }
+
+    fn parse_type_inner(&mut self) -> Result<Type, Error> {
+        match self.expect_one_of(TYPE_START)? {
+            Token::Identifier(name) => Ok(Type::Named(name.to_string())),
+            Token::LeftBracket => self.within(Construct::Type, |parser| {
+                let elem = parser.parse_type()?;
+                parser.expect_token(Token::RightBracket)?;
+                Ok(Type::Array(Box::new(elem)))
+            }),
+            Token::LeftBrace => self.within(Construct::Type, |parser| {
+                let key = parser.parse_type()?;
+                parser.expect_token(Token::Colon)?;
+                let value = parser.parse_type()?;
+                parser.expect_token(Token::RightBrace)?;
+                Ok(Type::Hash(Box::new(key), Box::new(value)))
+            }),
+            Token::Function => self.within(Construct::Type, |parser| {
+                parser.expect_token(Token::LeftParen)?;
+                let parameters = parser.parse_list(Token::RightParen, |parser| parser.parse_type())?;
+                parser.expect_token(Token::Arrow)?;
+                let ret = parser.parse_type()?;
+                Ok(Type::Function {
+                    parameters,
+                    ret: Box::new(ret),
+                })
+            }),
+            _ => unreachable!(),
         }
+    }
+
+    fn next_token(&mut self) -> Result<&'a Token, Error> {
+        let Some(tok) = self.tokens.next() else {
+            return Err(Error::EOF {
+                context: self.contexts.last().copied(),
+            });
+        };
+        self.position += 1;
+        self.expected.clear();
         Ok(tok)
     }
+
+    /// Tells whether the next token is of the same kind as `token`,
+    /// which is otherwise listed among the expected ones.
+    fn check(&mut self, token: Token) -> bool {
+        let found = self.tokens.peek().is_some_and(|tok| mem::discriminant(*tok) == mem::discriminant(&token));
+        if !found && !self.expected.contains(&token) {
+            self.expected.push(token);
+        }
+        found
+    }
+
+    /// Consumes the next token if it is of the same kind as `token`.
+    fn eat(&mut self, token: Token) -> Result<bool, Error> {
+        if !self.check(token) {
+            return Ok(false);
+        }
+        self.next_token()?;
+        Ok(true)
+    }
+
+    /// Builds the error for the next token, which is none of the expected ones.
+    fn mismatch(&mut self) -> Error {
+        Error::ExpectedToken(Box::new(Mismatch {
+            expected: mem::take(&mut self.expected),
+            found: self.tokens.peek().map(|tok| (*tok).clone()),
+            span: self.next_span(),
+            context: self.contexts.last().copied(),
+        }))
+    }
+
+    /// Returns the span of the next token, if known.
+    fn next_span(&self) -> Option<Span> {
+        self.spans.get(self.position).copied()
+    }
+
+    /// Returns the span from the first to the last of the tokens, if known.
+    fn span_of(&self, tokens: Range<usize>) -> Option<Span> {
+        let first = self.spans.get(tokens.start)?;
+        let last = self.spans.get(tokens.end.checked_sub(1)?)?;
+        Some(Span {
+            start: first.start,
+            end: last.end,
+        })
+    }
+
+    fn expect_identifier(&mut self) -> Result<String, Error> {
+        self.expect_token(Token::Identifier(String::default()))
+            .map(|tok| match tok {
+                Token::Identifier(s) => s.to_string(),
+                _ => unreachable!(),
+            })
+    }
+
+    fn expect_token(&mut self, token: Token) -> Result<&'a Token, Error> {
+        if !self.check(token) {
+            return Err(self.mismatch());
+        }
+        self.next_token()
+    }
+
+    /// Consumes the next token if it is of the same kind as one of `tokens`.
+    fn expect_one_of(&mut self, tokens: &[Token]) -> Result<&'a Token, Error> {
+        let found = self.tokens.peek().is_some_and(|tok| {
+            tokens.iter().any(|token| mem::discriminant(*tok) == mem::discriminant(token))
+        });
+        if !found {
+            for token in tokens {
+                self.check(token.clone());
+            }
+            return Err(self.mismatch());
+        }
+        self.next_token()
+    }
 }
 
-/// Contains the statements parsed before the error occurred, along
-/// with the error.
-struct PartialStatements {
-    statements: Vec<Statement>,
-    error: Error,
+impl<'a, T: Iterator<Item = &'a Token>> Parser<'a, T, Boxed> {
+    pub fn parse(&mut self) -> Result<AST, Error> {
+        self.parse_program().map(AST::new)
+    }
+
+    /// Parses a single expression, which must span all the tokens.
+    /// A trailing semicolon is not allowed either.
+    pub fn parse_expression(&mut self) -> Result<Expression, Error> {
+        let exp = self.parse_expression_at(Priority::Lowest)?;
+        if let Some(tok) = self.tokens.next() {
+            return Err(Error::TrailingToken {
+                token: tok.clone(),
+                span: self.next_span(),
+            });
+        }
+        Ok(exp)
+    }
+}
+
+impl<'a, T: Iterator<Item = &'a Token>> Parser<'a, T, Arena> {
+    /// Parses the program keeping its nodes in an arena, which
+    /// can be traversed by index instead of through boxes.
+    pub fn parse_arena(&mut self) -> Result<ArenaAST, Error> {
+        let mut statements = self.parse_program()?;
+        self.builder.renumber(&mut statements);
+        Ok(ArenaAST::new(mem::take(&mut self.builder), statements))
+    }
+}
+
+/// Returns the kind of syntax node for the expression `exp`, built from an operator `tok`.
+fn expression_kind<E, S>(tok: &Token, exp: &ExprNode<E, S>) -> NodeKind {
+    match exp {
+        ExprNode::None | ExprNode::Boolean(_) | ExprNode::Integer(_) | ExprNode::String(_) => NodeKind::Literal,
+        ExprNode::Identifier(_) => NodeKind::Name,
+        ExprNode::Unary { .. } => NodeKind::UnaryExpr,
+        ExprNode::Binary { .. } => NodeKind::BinaryExpr,
+        ExprNode::Logical { .. } => NodeKind::LogicalExpr,
+        ExprNode::Assign { .. } => NodeKind::AssignExpr,
+        ExprNode::Array(_) => NodeKind::ArrayExpr,
+        ExprNode::Hash(_) => NodeKind::HashExpr,
+        ExprNode::Index { .. } => NodeKind::IndexExpr,
+        ExprNode::If { .. } => NodeKind::IfExpr,
+        ExprNode::Match { .. } => NodeKind::MatchExpr,
+        ExprNode::Function { .. } if matches!(tok, Token::Bar | Token::Or) => NodeKind::LambdaExpr,
+        ExprNode::Function { .. } => NodeKind::FunctionExpr,
+        ExprNode::Macro { .. } => NodeKind::MacroExpr,
+        ExprNode::Call { .. } if tok == &Token::Pipe => NodeKind::PipeExpr,
+        ExprNode::Call { .. } => NodeKind::CallExpr,
+    }
+}
+
+/// Returns the kind of syntax node for the statement, which begins with `keyword`.
+fn statement_kind<E, S>(statement: &StmtNode<E, S>, keyword: &Token) -> NodeKind {
+    match statement {
+        // `fn name() {}` declares a function, whereas `fn() {}` begins an expression.
+        StmtNode::Let { .. } if keyword == &Token::Function => NodeKind::FunctionDeclaration,
+        StmtNode::Let { .. } => NodeKind::LetStatement,
+        StmtNode::Expression(_) => NodeKind::ExpressionStatement,
+        StmtNode::Return(_) => NodeKind::ReturnStatement,
+        StmtNode::While { .. } => NodeKind::WhileStatement,
+        StmtNode::For { .. } => NodeKind::ForStatement,
+        StmtNode::Break => NodeKind::BreakStatement,
+        StmtNode::Continue => NodeKind::ContinueStatement,
+        StmtNode::Import { .. } => NodeKind::ImportStatement,
+        StmtNode::Export(_) => NodeKind::ExportStatement,
+    }
 }
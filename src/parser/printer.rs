@@ -0,0 +1,440 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::{Expression, MatchArm, Parameter, Pattern, Priority, Statement, Type, AST};
+use crate::lexer::Token;
+
+const INDENT: &str = "    ";
+
+/// Writes the AST back as Monkey source code, indenting blocks
+/// and parenthesizing only where the parser requires it.
+struct Printer<'a, 'b> {
+    f: &'a mut Formatter<'b>,
+    indent: usize,
+}
+
+impl<'a, 'b> Printer<'a, 'b> {
+    fn new(f: &'a mut Formatter<'b>) -> Self {
+        Self { f, indent: 0 }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) -> fmt::Result {
+        for (i, st) in statements.iter().enumerate() {
+            if i > 0 {
+                self.newline()?;
+            }
+            self.statement(st)?;
+        }
+        Ok(())
+    }
+
+    fn block(&mut self, block: &[Statement]) -> fmt::Result {
+        if block.is_empty() {
+            return write!(self.f, "{}{}", Token::LeftBrace, Token::RightBrace);
+        }
+        write!(self.f, "{}", Token::LeftBrace)?;
+        self.indent += 1;
+        self.newline()?;
+        self.statements(block)?;
+        self.indent -= 1;
+        self.newline()?;
+        write!(self.f, "{}", Token::RightBrace)
+    }
+
+    fn function(
+        &mut self,
+        name: Option<&str>,
+        parameters: &[Parameter],
+        rest: Option<&str>,
+        ret: Option<&Type>,
+        body: &[Statement],
+    ) -> fmt::Result {
+        write!(self.f, "{}", Token::Function)?;
+        if let Some(name) = name {
+            write!(self.f, " {}", name)?;
+        }
+        write!(self.f, "{}", Token::LeftParen)?;
+        for (i, param) in parameters.iter().enumerate() {
+            if i > 0 {
+                write!(self.f, "{} ", Token::Comma)?;
+            }
+            write!(self.f, "{}", param.name)?;
+            if let Some(annotation) = &param.annotation {
+                write!(self.f, "{} {}", Token::Colon, annotation)?;
+            }
+            if let Some(default) = &param.default {
+                write!(self.f, " {} ", Token::Assign)?;
+                self.expression(default)?;
+            }
+        }
+        if let Some(rest) = rest {
+            if !parameters.is_empty() {
+                write!(self.f, "{} ", Token::Comma)?;
+            }
+            write!(self.f, "{}{}", Token::Ellipsis, rest)?;
+        }
+        write!(self.f, "{} ", Token::RightParen)?;
+        if let Some(ret) = ret {
+            write!(self.f, "{} {} ", Token::Arrow, ret)?;
+        }
+        self.block(body)
+    }
+
+    fn newline(&mut self) -> fmt::Result {
+        writeln!(self.f)?;
+        for _ in 0..self.indent {
+            write!(self.f, "{}", INDENT)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the doc comment of a documented binding, which goes before
+    /// any `export` keyword so that it can be parsed back.
+    fn doc(&mut self, st: &Statement) -> fmt::Result {
+        match st {
+            Statement::Let { doc: Some(doc), .. } => {
+                for line in doc.lines() {
+                    write!(self.f, "{}", Token::DocComment(line.to_string()))?;
+                    self.newline()?;
+                }
+                Ok(())
+            }
+            Statement::Export(st) => self.doc(st),
+            _ => Ok(()),
+        }
+    }
+
+    fn statement(&mut self, st: &Statement) -> fmt::Result {
+        self.doc(st)?;
+        self.undocumented_statement(st)
+    }
+
+    fn undocumented_statement(&mut self, st: &Statement) -> fmt::Result {
+        match st {
+            Statement::Expression(exp) => {
+                self.expression(exp)?;
+                write!(self.f, "{}", Token::Semicolon)
+            }
+            Statement::Let {
+                pattern: Pattern::Identifier(binding),
+                annotation: None,
+                expression:
+                    Expression::Function {
+                        name: Some(name),
+                        parameters,
+                        rest,
+                        ret,
+                        body,
+                    },
+                ..
+            } if binding == name => self.function(Some(name), parameters, rest.as_deref(), ret.as_ref(), body),
+            Statement::Let {
+                pattern,
+                annotation,
+                expression,
+                ..
+            } => {
+                write!(self.f, "{} {}", Token::Let, pattern)?;
+                if let Some(annotation) = annotation {
+                    write!(self.f, "{} {}", Token::Colon, annotation)?;
+                }
+                write!(self.f, " {} ", Token::Assign)?;
+                self.expression(expression)?;
+                write!(self.f, "{}", Token::Semicolon)
+            }
+            Statement::Return(Expression::None) => write!(self.f, "{}{}", Token::Return, Token::Semicolon),
+            Statement::Return(exp) => {
+                write!(self.f, "{} ", Token::Return)?;
+                self.expression(exp)?;
+                write!(self.f, "{}", Token::Semicolon)
+            }
+            Statement::While { cond, body } => {
+                write!(self.f, "{} {}", Token::While, Token::LeftParen)?;
+                self.expression(cond)?;
+                write!(self.f, "{} ", Token::RightParen)?;
+                self.block(body)
+            }
+            Statement::For {
+                variable,
+                iterable,
+                body,
+            } => {
+                write!(self.f, "{} {}{} {} ", Token::For, Token::LeftParen, variable, Token::In)?;
+                self.expression(iterable)?;
+                write!(self.f, "{} ", Token::RightParen)?;
+                self.block(body)
+            }
+            Statement::Break => write!(self.f, "{}{}", Token::Break, Token::Semicolon),
+            Statement::Continue => write!(self.f, "{}{}", Token::Continue, Token::Semicolon),
+            Statement::Import { path, alias } => write!(
+                self.f,
+                "{} \"{}\" {} {}{}",
+                Token::Import,
+                path,
+                Token::As,
+                alias,
+                Token::Semicolon
+            ),
+            Statement::Export(st) => {
+                write!(self.f, "{} ", Token::Export)?;
+                self.undocumented_statement(st)
+            }
+        }
+    }
+
+    /// Writes an operand of a larger expression, enclosing it in parentheses
+    /// when the parser would otherwise attach it to the surrounding operators.
+    fn operand(&mut self, exp: &Expression, parenthesize: bool) -> fmt::Result {
+        if !parenthesize {
+            return self.expression(exp);
+        }
+        write!(self.f, "{}", Token::LeftParen)?;
+        self.expression(exp)?;
+        write!(self.f, "{}", Token::RightParen)
+    }
+
+    fn expression(&mut self, exp: &Expression) -> fmt::Result {
+        match exp {
+            Expression::None => Ok(()),
+            Expression::Boolean(b) => write!(self.f, "{}", b),
+            Expression::Identifier(name) => write!(self.f, "{}", name),
+            Expression::Integer(int) => write!(self.f, "{}", int),
+            Expression::String(s) => write!(self.f, "\"{}\"", s),
+            Expression::Array(elems) => {
+                write!(self.f, "{}", Token::LeftBracket)?;
+                self.list(elems)?;
+                write!(self.f, "{}", Token::RightBracket)
+            }
+            Expression::Hash(pairs) => {
+                write!(self.f, "{}", Token::LeftBrace)?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        write!(self.f, "{} ", Token::Comma)?;
+                    }
+                    self.expression(key)?;
+                    write!(self.f, "{} ", Token::Colon)?;
+                    self.expression(value)?;
+                }
+                write!(self.f, "{}", Token::RightBrace)
+            }
+            Expression::Index { left, index } => {
+                self.operand(left, priority_of(left) < Priority::Call)?;
+                write!(self.f, "{}", Token::LeftBracket)?;
+                self.expression(index)?;
+                write!(self.f, "{}", Token::RightBracket)
+            }
+            Expression::Unary { operator, expression } => {
+                write!(self.f, "{}", operator)?;
+                self.operand(expression, priority_of(expression) < Priority::Unary)
+            }
+            Expression::Binary { left, operator, right } | Expression::Logical { left, operator, right } => {
+                // Operators are left-associative: a right operand
+                // of the same priority must be parenthesized.
+                let priority = Priority::from_token(operator);
+                self.operand(left, priority_of(left) < priority)?;
+                write!(self.f, " {} ", operator)?;
+                self.operand(right, priority_of(right) <= priority)
+            }
+            Expression::If { cond, conseq, altern } => {
+                write!(self.f, "{} {}", Token::If, Token::LeftParen)?;
+                self.expression(cond)?;
+                write!(self.f, "{} ", Token::RightParen)?;
+                self.block(conseq)?;
+                if let Some(altern) = altern {
+                    write!(self.f, " {} ", Token::Else)?;
+                    self.block(altern)?;
+                }
+                Ok(())
+            }
+            // Only declarations can name a function in the source.
+            Expression::Function {
+                parameters,
+                rest,
+                ret,
+                body,
+                ..
+            } => self.function(None, parameters, rest.as_deref(), ret.as_ref(), body),
+            Expression::Macro { parameters, body } => {
+                write!(
+                    self.f,
+                    "{}{}{}{} ",
+                    Token::Macro,
+                    Token::LeftParen,
+                    parameters.join(", "),
+                    Token::RightParen
+                )?;
+                self.block(body)
+            }
+            Expression::Call {
+                function,
+                arguments,
+                named,
+            } => {
+                self.operand(function, priority_of(function) < Priority::Call)?;
+                write!(self.f, "{}", Token::LeftParen)?;
+                self.list(arguments)?;
+                for (i, (name, arg)) in named.iter().enumerate() {
+                    if i > 0 || !arguments.is_empty() {
+                        write!(self.f, "{} ", Token::Comma)?;
+                    }
+                    write!(self.f, "{}{} ", name, Token::Colon)?;
+                    self.expression(arg)?;
+                }
+                write!(self.f, "{}", Token::RightParen)
+            }
+            Expression::Match { subject, arms } => {
+                write!(self.f, "{} {}", Token::Match, Token::LeftParen)?;
+                self.expression(subject)?;
+                write!(self.f, "{} {}", Token::RightParen, Token::LeftBrace)?;
+                self.indent += 1;
+                for arm in arms {
+                    self.newline()?;
+                    self.match_arm(arm)?;
+                    write!(self.f, "{}", Token::Comma)?;
+                }
+                self.indent -= 1;
+                if !arms.is_empty() {
+                    self.newline()?;
+                }
+                write!(self.f, "{}", Token::RightBrace)
+            }
+            Expression::Assign { target, operator, value } => {
+                self.operand(target, priority_of(target) < Priority::Call)?;
+                match operator {
+                    Some(operator) => write!(self.f, " {}{} ", operator, Token::Assign)?,
+                    None => write!(self.f, " {} ", Token::Assign)?,
+                }
+                self.expression(value)
+            }
+        }
+    }
+
+    fn list(&mut self, exps: &[Expression]) -> fmt::Result {
+        for (i, exp) in exps.iter().enumerate() {
+            if i > 0 {
+                write!(self.f, "{} ", Token::Comma)?;
+            }
+            self.expression(exp)?;
+        }
+        Ok(())
+    }
+
+    fn match_arm(&mut self, arm: &MatchArm) -> fmt::Result {
+        write!(self.f, "{} ", arm.pattern)?;
+        if let Some(guard) = &arm.guard {
+            write!(self.f, "{} ", Token::If)?;
+            self.expression(guard)?;
+            write!(self.f, " ")?;
+        }
+        write!(self.f, "{} ", Token::FatArrow)?;
+        self.expression(&arm.body)
+    }
+}
+
+/// Returns the priority at which the parser builds the expression.
+/// Expressions that are not made of operators bind the tightest.
+fn priority_of(exp: &Expression) -> Priority {
+    match exp {
+        Expression::Binary { operator, .. } | Expression::Logical { operator, .. } => Priority::from_token(operator),
+        Expression::Unary { .. } => Priority::Unary,
+        Expression::Assign { .. } => Priority::Assign,
+        _ => Priority::Call,
+    }
+}
+
+impl Display for AST {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Printer::new(f).statements(self.statements())
+    }
+}
+
+impl Display for Statement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Printer::new(f).statement(self)
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Printer::new(f).expression(self)
+    }
+}
+
+impl Display for MatchArm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Printer::new(f).match_arm(self)
+    }
+}
+
+impl Display for Parameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(annotation) = &self.annotation {
+            write!(f, "{} {}", Token::Colon, annotation)?;
+        }
+        if let Some(default) = &self.default {
+            write!(f, " {} {}", Token::Assign, default)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Identifier(name) => write!(f, "{}", name),
+            Pattern::Boolean(b) => write!(f, "{}", b),
+            Pattern::Integer(int) => write!(f, "{}", int),
+            Pattern::String(s) => write!(f, "\"{}\"", s),
+            Pattern::Array { elements, rest } => {
+                write!(f, "{}", Token::LeftBracket)?;
+                for (i, elem) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{}", elem)?;
+                }
+                if let Some(rest) = rest {
+                    if !elements.is_empty() {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{}{}", Token::Ellipsis, rest)?;
+                }
+                write!(f, "{}", Token::RightBracket)
+            }
+            Pattern::Hash(pairs) => {
+                write!(f, "{}", Token::LeftBrace)?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    match (key, value) {
+                        (Expression::String(key), Pattern::Identifier(name)) if key == name => write!(f, "{}", name)?,
+                        _ => write!(f, "{}{} {}", key, Token::Colon, value)?,
+                    }
+                }
+                write!(f, "{}", Token::RightBrace)
+            }
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Array(elem) => write!(f, "{}{}{}", Token::LeftBracket, elem, Token::RightBracket),
+            Type::Hash(key, value) => write!(f, "{}{}{} {}{}", Token::LeftBrace, key, Token::Colon, value, Token::RightBrace),
+            Type::Function { parameters, ret } => {
+                write!(f, "{}{}", Token::Function, Token::LeftParen)?;
+                for (i, param) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{} ", Token::Comma)?;
+                    }
+                    write!(f, "{}", param)?;
+                }
+                write!(f, "{} {} {}", Token::RightParen, Token::Arrow, ret)
+            }
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use super::{Expression, MatchArm, Parameter, Pattern, Statement, Type, AST};
+use crate::lexer::Token;
+
+/// Writes the AST as an S-expression, for tools that want the tree
+/// without linking to this crate.
+///
+/// Every compound node is a list headed by its kind, like `(let x 5)`
+/// or `(call f 1 2)`. Operators are written as their token, like `(+ a b)`,
+/// and optional children that are absent are omitted: an `if` without
+/// `else` is `(if cond (block ...))`.
+pub fn to_sexp(ast: &AST) -> String {
+    let mut writer = Writer::default();
+    writer.open("program");
+    for st in ast.statements() {
+        writer.statement(st);
+    }
+    writer.close();
+    writer.out
+}
+
+#[derive(Default)]
+struct Writer {
+    out: String,
+}
+
+impl Writer {
+    fn open(&mut self, head: &str) {
+        self.separate();
+        self.out.push('(');
+        self.out.push_str(head);
+    }
+
+    fn close(&mut self) {
+        self.out.push(')');
+    }
+
+    fn atom(&mut self, atom: &str) {
+        self.separate();
+        self.out.push_str(atom);
+    }
+
+    fn string(&mut self, s: &str) {
+        self.separate();
+        self.out.push('"');
+        for ch in s.chars() {
+            if ch == '"' || ch == '\\' {
+                self.out.push('\\');
+            }
+            self.out.push(ch);
+        }
+        self.out.push('"');
+    }
+
+    /// Adds a space between siblings.
+    fn separate(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('(') {
+            self.out.push(' ');
+        }
+    }
+
+    fn block(&mut self, statements: &[Statement]) {
+        self.open("block");
+        for st in statements {
+            self.statement(st);
+        }
+        self.close();
+    }
+
+    fn statement(&mut self, st: &Statement) {
+        match st {
+            Statement::Expression(exp) => {
+                self.open("expression");
+                self.expression(exp);
+            }
+            Statement::Let {
+                pattern,
+                annotation,
+                expression,
+                doc,
+            } => {
+                self.open(&Token::Let.to_string());
+                if let Some(doc) = doc {
+                    self.open("doc");
+                    self.string(doc);
+                    self.close();
+                }
+                self.pattern(pattern);
+                if let Some(annotation) = annotation {
+                    self.open("type");
+                    self.type_(annotation);
+                    self.close();
+                }
+                self.expression(expression);
+            }
+            Statement::Return(exp) => {
+                self.open(&Token::Return.to_string());
+                self.expression(exp);
+            }
+            Statement::While { cond, body } => {
+                self.open(&Token::While.to_string());
+                self.expression(cond);
+                self.block(body);
+            }
+            Statement::For {
+                variable,
+                iterable,
+                body,
+            } => {
+                self.open(&Token::For.to_string());
+                self.atom(variable);
+                self.expression(iterable);
+                self.block(body);
+            }
+            Statement::Break => self.open(&Token::Break.to_string()),
+            Statement::Continue => self.open(&Token::Continue.to_string()),
+            Statement::Import { path, alias } => {
+                self.open(&Token::Import.to_string());
+                self.string(path);
+                self.atom(alias);
+            }
+            Statement::Export(st) => {
+                self.open(&Token::Export.to_string());
+                self.statement(st);
+            }
+        }
+        self.close();
+    }
+
+    fn expression(&mut self, exp: &Expression) {
+        match exp {
+            Expression::None => return self.atom("()"),
+            Expression::Boolean(b) => return self.atom(&b.to_string()),
+            Expression::Identifier(name) => return self.atom(name),
+            Expression::Integer(int) => return self.atom(&int.to_string()),
+            Expression::String(s) => return self.string(s),
+            Expression::Array(elems) => {
+                self.open("array");
+                for elem in elems {
+                    self.expression(elem);
+                }
+            }
+            Expression::Hash(pairs) => {
+                self.open("hash");
+                for (key, value) in pairs {
+                    self.open("");
+                    self.expression(key);
+                    self.expression(value);
+                    self.close();
+                }
+            }
+            Expression::Index { left, index } => {
+                self.open("index");
+                self.expression(left);
+                self.expression(index);
+            }
+            Expression::Unary { operator, expression } => {
+                self.open(&operator.to_string());
+                self.expression(expression);
+            }
+            Expression::Binary { left, operator, right } | Expression::Logical { left, operator, right } => {
+                self.open(&operator.to_string());
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::If { cond, conseq, altern } => {
+                self.open(&Token::If.to_string());
+                self.expression(cond);
+                self.block(conseq);
+                if let Some(altern) = altern {
+                    self.block(altern);
+                }
+            }
+            Expression::Function {
+                name,
+                parameters,
+                rest,
+                ret,
+                body,
+            } => {
+                self.open(&Token::Function.to_string());
+                if let Some(name) = name {
+                    self.atom(name);
+                }
+                self.open("params");
+                for param in parameters {
+                    self.parameter(param);
+                }
+                if let Some(rest) = rest {
+                    self.open(&Token::Ellipsis.to_string());
+                    self.atom(rest);
+                    self.close();
+                }
+                self.close();
+                if let Some(ret) = ret {
+                    self.open("type");
+                    self.type_(ret);
+                    self.close();
+                }
+                self.block(body);
+            }
+            Expression::Macro { parameters, body } => {
+                self.open(&Token::Macro.to_string());
+                self.open("params");
+                for param in parameters {
+                    self.atom(param);
+                }
+                self.close();
+                self.block(body);
+            }
+            Expression::Call {
+                function,
+                arguments,
+                named,
+            } => {
+                self.open("call");
+                self.expression(function);
+                for arg in arguments {
+                    self.expression(arg);
+                }
+                for (name, arg) in named {
+                    self.open("named");
+                    self.atom(name);
+                    self.expression(arg);
+                    self.close();
+                }
+            }
+            Expression::Match { subject, arms } => {
+                self.open(&Token::Match.to_string());
+                self.expression(subject);
+                for arm in arms {
+                    self.match_arm(arm);
+                }
+            }
+            Expression::Assign { target, operator, value } => {
+                match operator {
+                    Some(operator) => self.open(&format!("{}{}", operator, Token::Assign)),
+                    None => self.open(&Token::Assign.to_string()),
+                }
+                self.expression(target);
+                self.expression(value);
+            }
+        }
+        self.close();
+    }
+
+    /// Writes a bare parameter as its name, and one with an annotation
+    /// or a default value as a list like `(x int (default 1))`.
+    fn parameter(&mut self, param: &Parameter) {
+        if param.annotation.is_none() && param.default.is_none() {
+            return self.atom(&param.name);
+        }
+        self.open(&param.name);
+        if let Some(annotation) = &param.annotation {
+            self.type_(annotation);
+        }
+        if let Some(default) = &param.default {
+            self.open("default");
+            self.expression(default);
+            self.close();
+        }
+        self.close();
+    }
+
+    fn match_arm(&mut self, arm: &MatchArm) {
+        self.open("arm");
+        self.pattern(&arm.pattern);
+        if let Some(guard) = &arm.guard {
+            self.open(&Token::If.to_string());
+            self.expression(guard);
+            self.close();
+        }
+        self.expression(&arm.body);
+        self.close();
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Wildcard => self.atom("_"),
+            Pattern::Identifier(name) => self.atom(name),
+            Pattern::Boolean(b) => self.atom(&b.to_string()),
+            Pattern::Integer(int) => self.atom(&int.to_string()),
+            Pattern::String(s) => self.string(s),
+            Pattern::Array { elements, rest } => {
+                self.open("array");
+                for elem in elements {
+                    self.pattern(elem);
+                }
+                if let Some(rest) = rest {
+                    self.open(&Token::Ellipsis.to_string());
+                    self.atom(rest);
+                    self.close();
+                }
+                self.close();
+            }
+            Pattern::Hash(pairs) => {
+                self.open("hash");
+                for (key, value) in pairs {
+                    self.open("");
+                    self.expression(key);
+                    self.pattern(value);
+                    self.close();
+                }
+                self.close();
+            }
+        }
+    }
+
+    fn type_(&mut self, t: &Type) {
+        match t {
+            Type::Named(name) => self.atom(name),
+            Type::Array(elem) => {
+                self.open("array");
+                self.type_(elem);
+                self.close();
+            }
+            Type::Hash(key, value) => {
+                self.open("hash");
+                self.type_(key);
+                self.type_(value);
+                self.close();
+            }
+            Type::Function { parameters, ret } => {
+                self.open(&Token::Function.to_string());
+                self.open("");
+                for param in parameters {
+                    self.type_(param);
+                }
+                self.close();
+                self.type_(ret);
+                self.close();
+            }
+        }
+    }
+}
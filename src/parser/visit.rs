@@ -0,0 +1,489 @@
+//! Traversals over the AST.
+//!
+//! Each trait has a method per kind of node, whose default implementation
+//! calls the matching `walk_*` function to descend into the children.
+//! A pass overrides only the methods for the nodes it cares about, and
+//! calls the `walk_*` function itself when it still wants to descend.
+//! Every block of statements, including both branches of an `if`,
+//! goes through the block method.
+
+use super::{Expression, MatchArm, Parameter, Pattern, Statement};
+
+/// Visits the AST by shared reference. The visitor may keep
+/// references to the nodes, which live as long as `'ast`.
+pub trait Visitor<'ast> {
+    fn visit_block(&mut self, block: &'ast [Statement]) {
+        walk_block(self, block)
+    }
+
+    fn visit_statement(&mut self, st: &'ast Statement) {
+        walk_statement(self, st)
+    }
+
+    fn visit_expression(&mut self, exp: &'ast Expression) {
+        walk_expression(self, exp)
+    }
+
+    fn visit_match_arm(&mut self, arm: &'ast MatchArm) {
+        walk_match_arm(self, arm)
+    }
+
+    fn visit_pattern(&mut self, pattern: &'ast Pattern) {
+        walk_pattern(self, pattern)
+    }
+}
+
+pub fn walk_block<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, block: &'ast [Statement]) {
+    for st in block {
+        visitor.visit_statement(st);
+    }
+}
+
+pub fn walk_statement<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, st: &'ast Statement) {
+    match st {
+        Statement::Expression(exp) | Statement::Return(exp) => visitor.visit_expression(exp),
+        Statement::Let { pattern, expression, .. } => {
+            visitor.visit_pattern(pattern);
+            visitor.visit_expression(expression);
+        }
+        Statement::While { cond, body } => {
+            visitor.visit_expression(cond);
+            visitor.visit_block(body);
+        }
+        Statement::For { iterable, body, .. } => {
+            visitor.visit_expression(iterable);
+            visitor.visit_block(body);
+        }
+        Statement::Export(st) => visitor.visit_statement(st),
+        Statement::Break | Statement::Continue | Statement::Import { .. } => {}
+    }
+}
+
+pub fn walk_expression<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, exp: &'ast Expression) {
+    match exp {
+        Expression::Array(elems) => {
+            for elem in elems {
+                visitor.visit_expression(elem);
+            }
+        }
+        Expression::Hash(pairs) => {
+            for (key, value) in pairs {
+                visitor.visit_expression(key);
+                visitor.visit_expression(value);
+            }
+        }
+        Expression::Index { left, index } => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(index);
+        }
+        Expression::Unary { expression, .. } => visitor.visit_expression(expression),
+        Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::If { cond, conseq, altern } => {
+            visitor.visit_expression(cond);
+            visitor.visit_block(conseq);
+            if let Some(altern) = altern {
+                visitor.visit_block(altern);
+            }
+        }
+        Expression::Function { parameters, body, .. } => {
+            for default in parameters.iter().filter_map(|param| param.default.as_ref()) {
+                visitor.visit_expression(default);
+            }
+            visitor.visit_block(body);
+        }
+        Expression::Macro { body, .. } => visitor.visit_block(body),
+        Expression::Call {
+            function,
+            arguments,
+            named,
+        } => {
+            visitor.visit_expression(function);
+            for arg in arguments {
+                visitor.visit_expression(arg);
+            }
+            for (_, arg) in named {
+                visitor.visit_expression(arg);
+            }
+        }
+        Expression::Match { subject, arms } => {
+            visitor.visit_expression(subject);
+            for arm in arms {
+                visitor.visit_match_arm(arm);
+            }
+        }
+        Expression::Assign { target, value, .. } => {
+            visitor.visit_expression(target);
+            visitor.visit_expression(value);
+        }
+        Expression::None
+        | Expression::Boolean(_)
+        | Expression::Identifier(_)
+        | Expression::Integer(_)
+        | Expression::String(_) => {}
+    }
+}
+
+pub fn walk_match_arm<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, arm: &'ast MatchArm) {
+    visitor.visit_pattern(&arm.pattern);
+    if let Some(guard) = &arm.guard {
+        visitor.visit_expression(guard);
+    }
+    visitor.visit_expression(&arm.body);
+}
+
+/// Visits the nested patterns. The keys of a hash pattern are literals
+/// rather than code, so they are not visited as expressions.
+pub fn walk_pattern<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, pattern: &'ast Pattern) {
+    match pattern {
+        Pattern::Array { elements, .. } => {
+            for elem in elements {
+                visitor.visit_pattern(elem);
+            }
+        }
+        Pattern::Hash(pairs) => {
+            for (_, value) in pairs {
+                visitor.visit_pattern(value);
+            }
+        }
+        Pattern::Wildcard | Pattern::Identifier(_) | Pattern::Boolean(_) | Pattern::Integer(_) | Pattern::String(_) => {}
+    }
+}
+
+/// Visits the AST by mutable reference, to edit it in place.
+pub trait MutVisitor {
+    fn visit_block_mut(&mut self, block: &mut Vec<Statement>) {
+        walk_block_mut(self, block)
+    }
+
+    fn visit_statement_mut(&mut self, st: &mut Statement) {
+        walk_statement_mut(self, st)
+    }
+
+    fn visit_expression_mut(&mut self, exp: &mut Expression) {
+        walk_expression_mut(self, exp)
+    }
+
+    fn visit_match_arm_mut(&mut self, arm: &mut MatchArm) {
+        walk_match_arm_mut(self, arm)
+    }
+
+    fn visit_pattern_mut(&mut self, pattern: &mut Pattern) {
+        walk_pattern_mut(self, pattern)
+    }
+}
+
+pub fn walk_block_mut<V: MutVisitor + ?Sized>(visitor: &mut V, block: &mut Vec<Statement>) {
+    for st in block {
+        visitor.visit_statement_mut(st);
+    }
+}
+
+pub fn walk_statement_mut<V: MutVisitor + ?Sized>(visitor: &mut V, st: &mut Statement) {
+    match st {
+        Statement::Expression(exp) | Statement::Return(exp) => visitor.visit_expression_mut(exp),
+        Statement::Let { pattern, expression, .. } => {
+            visitor.visit_pattern_mut(pattern);
+            visitor.visit_expression_mut(expression);
+        }
+        Statement::While { cond, body } => {
+            visitor.visit_expression_mut(cond);
+            visitor.visit_block_mut(body);
+        }
+        Statement::For { iterable, body, .. } => {
+            visitor.visit_expression_mut(iterable);
+            visitor.visit_block_mut(body);
+        }
+        Statement::Export(st) => visitor.visit_statement_mut(st),
+        Statement::Break | Statement::Continue | Statement::Import { .. } => {}
+    }
+}
+
+pub fn walk_expression_mut<V: MutVisitor + ?Sized>(visitor: &mut V, exp: &mut Expression) {
+    match exp {
+        Expression::Array(elems) => {
+            for elem in elems {
+                visitor.visit_expression_mut(elem);
+            }
+        }
+        Expression::Hash(pairs) => {
+            for (key, value) in pairs {
+                visitor.visit_expression_mut(key);
+                visitor.visit_expression_mut(value);
+            }
+        }
+        Expression::Index { left, index } => {
+            visitor.visit_expression_mut(left);
+            visitor.visit_expression_mut(index);
+        }
+        Expression::Unary { expression, .. } => visitor.visit_expression_mut(expression),
+        Expression::Binary { left, right, .. } | Expression::Logical { left, right, .. } => {
+            visitor.visit_expression_mut(left);
+            visitor.visit_expression_mut(right);
+        }
+        Expression::If { cond, conseq, altern } => {
+            visitor.visit_expression_mut(cond);
+            visitor.visit_block_mut(conseq);
+            if let Some(altern) = altern {
+                visitor.visit_block_mut(altern);
+            }
+        }
+        Expression::Function { parameters, body, .. } => {
+            for default in parameters.iter_mut().filter_map(|param| param.default.as_mut()) {
+                visitor.visit_expression_mut(default);
+            }
+            visitor.visit_block_mut(body);
+        }
+        Expression::Macro { body, .. } => visitor.visit_block_mut(body),
+        Expression::Call {
+            function,
+            arguments,
+            named,
+        } => {
+            visitor.visit_expression_mut(function);
+            for arg in arguments {
+                visitor.visit_expression_mut(arg);
+            }
+            for (_, arg) in named {
+                visitor.visit_expression_mut(arg);
+            }
+        }
+        Expression::Match { subject, arms } => {
+            visitor.visit_expression_mut(subject);
+            for arm in arms {
+                visitor.visit_match_arm_mut(arm);
+            }
+        }
+        Expression::Assign { target, value, .. } => {
+            visitor.visit_expression_mut(target);
+            visitor.visit_expression_mut(value);
+        }
+        Expression::None
+        | Expression::Boolean(_)
+        | Expression::Identifier(_)
+        | Expression::Integer(_)
+        | Expression::String(_) => {}
+    }
+}
+
+pub fn walk_match_arm_mut<V: MutVisitor + ?Sized>(visitor: &mut V, arm: &mut MatchArm) {
+    visitor.visit_pattern_mut(&mut arm.pattern);
+    if let Some(guard) = &mut arm.guard {
+        visitor.visit_expression_mut(guard);
+    }
+    visitor.visit_expression_mut(&mut arm.body);
+}
+
+pub fn walk_pattern_mut<V: MutVisitor + ?Sized>(visitor: &mut V, pattern: &mut Pattern) {
+    match pattern {
+        Pattern::Array { elements, .. } => {
+            for elem in elements {
+                visitor.visit_pattern_mut(elem);
+            }
+        }
+        Pattern::Hash(pairs) => {
+            for (_, value) in pairs {
+                visitor.visit_pattern_mut(value);
+            }
+        }
+        Pattern::Wildcard | Pattern::Identifier(_) | Pattern::Boolean(_) | Pattern::Integer(_) | Pattern::String(_) => {}
+    }
+}
+
+/// Rebuilds the AST by value. Unlike a [`MutVisitor`], a folder can
+/// replace a node with one of a different kind, and can fail: infallible
+/// folders use [`std::convert::Infallible`] as their error.
+pub trait Folder {
+    type Error;
+
+    fn fold_block(&mut self, block: Vec<Statement>) -> Result<Vec<Statement>, Self::Error> {
+        walk_block_fold(self, block)
+    }
+
+    fn fold_statement(&mut self, st: Statement) -> Result<Statement, Self::Error> {
+        walk_statement_fold(self, st)
+    }
+
+    fn fold_expression(&mut self, exp: Expression) -> Result<Expression, Self::Error> {
+        walk_expression_fold(self, exp)
+    }
+
+    fn fold_match_arm(&mut self, arm: MatchArm) -> Result<MatchArm, Self::Error> {
+        walk_match_arm_fold(self, arm)
+    }
+
+    fn fold_pattern(&mut self, pattern: Pattern) -> Result<Pattern, Self::Error> {
+        walk_pattern_fold(self, pattern)
+    }
+}
+
+pub fn walk_block_fold<F: Folder + ?Sized>(folder: &mut F, block: Vec<Statement>) -> Result<Vec<Statement>, F::Error> {
+    block.into_iter().map(|st| folder.fold_statement(st)).collect()
+}
+
+pub fn walk_statement_fold<F: Folder + ?Sized>(folder: &mut F, st: Statement) -> Result<Statement, F::Error> {
+    Ok(match st {
+        Statement::Expression(exp) => Statement::Expression(folder.fold_expression(exp)?),
+        Statement::Let {
+            pattern,
+            annotation,
+            expression,
+            doc,
+        } => Statement::Let {
+            pattern: folder.fold_pattern(pattern)?,
+            annotation,
+            expression: folder.fold_expression(expression)?,
+            doc,
+        },
+        Statement::Return(exp) => Statement::Return(folder.fold_expression(exp)?),
+        Statement::While { cond, body } => Statement::While {
+            cond: folder.fold_expression(cond)?,
+            body: folder.fold_block(body)?,
+        },
+        Statement::For {
+            variable,
+            iterable,
+            body,
+        } => Statement::For {
+            variable,
+            iterable: folder.fold_expression(iterable)?,
+            body: folder.fold_block(body)?,
+        },
+        Statement::Export(st) => Statement::Export(Box::new(folder.fold_statement(*st)?)),
+        st @ (Statement::Break | Statement::Continue | Statement::Import { .. }) => st,
+    })
+}
+
+pub fn walk_expression_fold<F: Folder + ?Sized>(folder: &mut F, exp: Expression) -> Result<Expression, F::Error> {
+    Ok(match exp {
+        Expression::Array(elems) => Expression::Array(fold_all(folder, elems)?),
+        Expression::Hash(pairs) => Expression::Hash(
+            pairs
+                .into_iter()
+                .map(|(key, value)| Ok((folder.fold_expression(key)?, folder.fold_expression(value)?)))
+                .collect::<Result<_, _>>()?,
+        ),
+        Expression::Index { left, index } => Expression::Index {
+            left: fold_boxed(folder, *left)?,
+            index: fold_boxed(folder, *index)?,
+        },
+        Expression::Unary { operator, expression } => Expression::Unary {
+            operator,
+            expression: fold_boxed(folder, *expression)?,
+        },
+        Expression::Binary { left, operator, right } => Expression::Binary {
+            left: fold_boxed(folder, *left)?,
+            operator,
+            right: fold_boxed(folder, *right)?,
+        },
+        Expression::Logical { left, operator, right } => Expression::Logical {
+            left: fold_boxed(folder, *left)?,
+            operator,
+            right: fold_boxed(folder, *right)?,
+        },
+        Expression::If { cond, conseq, altern } => Expression::If {
+            cond: fold_boxed(folder, *cond)?,
+            conseq: folder.fold_block(conseq)?,
+            altern: altern.map(|alt| folder.fold_block(alt)).transpose()?,
+        },
+        Expression::Function {
+            name,
+            parameters,
+            rest,
+            ret,
+            body,
+        } => Expression::Function {
+            name,
+            parameters: parameters
+                .into_iter()
+                .map(|param| {
+                    Ok(Parameter {
+                        default: param.default.map(|default| folder.fold_expression(default)).transpose()?,
+                        ..param
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            rest,
+            ret,
+            body: folder.fold_block(body)?,
+        },
+        Expression::Macro { parameters, body } => Expression::Macro {
+            parameters,
+            body: folder.fold_block(body)?,
+        },
+        Expression::Call {
+            function,
+            arguments,
+            named,
+        } => Expression::Call {
+            function: fold_boxed(folder, *function)?,
+            arguments: fold_all(folder, arguments)?,
+            named: named
+                .into_iter()
+                .map(|(name, arg)| Ok((name, folder.fold_expression(arg)?)))
+                .collect::<Result<_, _>>()?,
+        },
+        Expression::Match { subject, arms } => Expression::Match {
+            subject: fold_boxed(folder, *subject)?,
+            arms: arms
+                .into_iter()
+                .map(|arm| folder.fold_match_arm(arm))
+                .collect::<Result<_, _>>()?,
+        },
+        Expression::Assign {
+            target,
+            operator,
+            value,
+        } => Expression::Assign {
+            target: fold_boxed(folder, *target)?,
+            operator,
+            value: fold_boxed(folder, *value)?,
+        },
+        exp @ (Expression::None
+        | Expression::Boolean(_)
+        | Expression::Identifier(_)
+        | Expression::Integer(_)
+        | Expression::String(_)) => exp,
+    })
+}
+
+pub fn walk_match_arm_fold<F: Folder + ?Sized>(folder: &mut F, arm: MatchArm) -> Result<MatchArm, F::Error> {
+    Ok(MatchArm {
+        pattern: folder.fold_pattern(arm.pattern)?,
+        guard: arm.guard.map(|guard| folder.fold_expression(guard)).transpose()?,
+        body: folder.fold_expression(arm.body)?,
+    })
+}
+
+pub fn walk_pattern_fold<F: Folder + ?Sized>(folder: &mut F, pattern: Pattern) -> Result<Pattern, F::Error> {
+    Ok(match pattern {
+        Pattern::Array { elements, rest } => Pattern::Array {
+            elements: elements
+                .into_iter()
+                .map(|elem| folder.fold_pattern(elem))
+                .collect::<Result<_, _>>()?,
+            rest,
+        },
+        Pattern::Hash(pairs) => Pattern::Hash(
+            pairs
+                .into_iter()
+                .map(|(key, value)| Ok((key, folder.fold_pattern(value)?)))
+                .collect::<Result<_, _>>()?,
+        ),
+        pattern @ (Pattern::Wildcard
+        | Pattern::Identifier(_)
+        | Pattern::Boolean(_)
+        | Pattern::Integer(_)
+        | Pattern::String(_)) => pattern,
+    })
+}
+
+fn fold_boxed<F: Folder + ?Sized>(folder: &mut F, exp: Expression) -> Result<Box<Expression>, F::Error> {
+    folder.fold_expression(exp).map(Box::new)
+}
+
+fn fold_all<F: Folder + ?Sized>(folder: &mut F, exps: Vec<Expression>) -> Result<Vec<Expression>, F::Error> {
+    exps.into_iter().map(|exp| folder.fold_expression(exp)).collect()
+}
@@ -0,0 +1,20 @@
+use std::fmt;
+
+use crate::parser::{Expression, Type};
+
+#[derive(thiserror::Error, fmt::Debug, PartialEq, Eq)]
+pub enum Error {
+    #[error("expected {expected:?}, found {found:?} in {expression:?}")]
+    Mismatch {
+        expected: Type,
+        found: Type,
+        expression: Expression,
+    },
+
+    #[error("expected {expected} arguments, got {got} in {expression:?}")]
+    ArgumentCount {
+        expected: usize,
+        got: usize,
+        expression: Expression,
+    },
+}
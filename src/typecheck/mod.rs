@@ -0,0 +1,413 @@
+mod error;
+pub use error::*;
+
+use std::collections::HashMap;
+
+use crate::lexer::Token;
+use crate::parser::{Expression, Pattern, Statement, Type, AST};
+
+const INT: &str = "int";
+const BOOL: &str = "bool";
+const STRING: &str = "string";
+
+/// Checks that annotated bindings, function arguments and return values
+/// have the annotated types, and returns every mismatch found. Assignments
+/// must keep the type of an annotated binding, while an unannotated one
+/// has an unknown type once assigned to.
+///
+/// Types are only inferred from literals, from annotated bindings and from
+/// calls to annotated functions: code whose type cannot be inferred, as well
+/// as types other than `int`, `bool` and `string` referred to by name,
+/// are never reported.
+pub fn check(ast: &AST) -> Vec<Error> {
+    let mut checker = Checker::default();
+    checker.check_block(ast.statements());
+    checker.errors
+}
+
+#[derive(Default)]
+struct Binding {
+    /// The type of the value bound, if known.
+    t: Option<Type>,
+    /// Whether the type was annotated rather than inferred.
+    annotated: bool,
+}
+
+#[derive(Default)]
+struct Checker {
+    /// The bindings in each nested scope.
+    scopes: Vec<HashMap<String, Binding>>,
+    /// The return type of each enclosing function, if annotated.
+    rets: Vec<Option<Type>>,
+    errors: Vec<Error>,
+}
+
+impl Checker {
+    /// Checks the statements in a new scope, and returns the type
+    /// of the last one if it is an expression statement.
+    fn check_block(&mut self, statements: &[Statement]) -> Option<Type> {
+        self.scopes.push(HashMap::new());
+        let mut last = None;
+        for st in statements {
+            last = self.check_statement(st);
+        }
+        self.scopes.pop();
+        last
+    }
+
+    /// Checks the statement, and returns its type if it is an expression statement.
+    fn check_statement(&mut self, st: &Statement) -> Option<Type> {
+        match st {
+            Statement::Expression(exp) => return self.infer(exp),
+            Statement::Let {
+                pattern,
+                annotation,
+                expression,
+                ..
+            } => {
+                let found = self.infer(expression);
+                if let Some(annotation) = annotation {
+                    self.expect(annotation, found.as_ref(), expression);
+                }
+                match pattern {
+                    Pattern::Identifier(name) => self.bind(
+                        name,
+                        Binding {
+                            t: annotation.clone().or(found),
+                            annotated: annotation.is_some(),
+                        },
+                    ),
+                    pattern => self.bind_pattern(pattern),
+                }
+            }
+            Statement::Return(exp) => {
+                let found = self.infer(exp);
+                if let Some(Some(ret)) = self.rets.last().cloned() {
+                    self.expect(&ret, found.as_ref(), exp);
+                }
+            }
+            Statement::While { cond, body } => {
+                self.infer(cond);
+                self.check_block(body);
+            }
+            Statement::For {
+                variable,
+                iterable,
+                body,
+            } => {
+                let elem = match self.infer(iterable) {
+                    Some(Type::Array(elem)) => Some(*elem),
+                    _ => None,
+                };
+                let binding = Binding {
+                    t: elem,
+                    annotated: false,
+                };
+                self.scopes.push(HashMap::from([(variable.clone(), binding)]));
+                self.check_block(body);
+                self.scopes.pop();
+            }
+            Statement::Export(st) => {
+                self.check_statement(st);
+            }
+            Statement::Break | Statement::Continue | Statement::Import { .. } => {}
+        }
+        None
+    }
+
+    /// Returns the type of the expression if it can be inferred,
+    /// checking its sub-expressions along the way.
+    fn infer(&mut self, exp: &Expression) -> Option<Type> {
+        match exp {
+            Expression::None | Expression::Macro { .. } => None,
+            Expression::Boolean(_) => Some(Type::Named(BOOL.to_string())),
+            Expression::Integer(_) => Some(Type::Named(INT.to_string())),
+            Expression::String(_) => Some(Type::Named(STRING.to_string())),
+            Expression::Identifier(name) => self.lookup(name),
+            Expression::Array(elems) => {
+                let types: Vec<_> = elems.iter().map(|elem| self.infer(elem)).collect();
+                same_type(types).map(|elem| Type::Array(Box::new(elem)))
+            }
+            Expression::Hash(pairs) => {
+                let (keys, values): (Vec<_>, Vec<_>) = pairs
+                    .iter()
+                    .map(|(key, value)| (self.infer(key), self.infer(value)))
+                    .unzip();
+                Some(Type::Hash(Box::new(same_type(keys)?), Box::new(same_type(values)?)))
+            }
+            Expression::Index { left, index } => {
+                let left = self.infer(left);
+                self.infer(index);
+                match left? {
+                    Type::Array(elem) => Some(*elem),
+                    Type::Hash(_, value) => Some(*value),
+                    _ => None,
+                }
+            }
+            Expression::Unary { operator, expression } => {
+                let found = self.infer(expression);
+                match operator {
+                    Token::Bang => Some(Type::Named(BOOL.to_string())),
+                    _ => found.filter(|t| is_named(t, INT)),
+                }
+            }
+            Expression::Binary { left, operator, right } => {
+                // Both operands are checked, even if one has an unknown type.
+                let (left, right) = (self.infer(left), self.infer(right));
+                binary_type(operator, left?, right?)
+            }
+            Expression::Logical { left, right, .. } => {
+                self.infer(left);
+                self.infer(right);
+                Some(Type::Named(BOOL.to_string()))
+            }
+            Expression::If { cond, conseq, altern } => {
+                self.infer(cond);
+                self.check_block(conseq);
+                if let Some(altern) = altern {
+                    self.check_block(altern);
+                }
+                None
+            }
+            Expression::Function {
+                name,
+                parameters,
+                rest,
+                ret,
+                body,
+            } => {
+                for param in parameters {
+                    let Some(default) = &param.default else {
+                        continue;
+                    };
+                    let found = self.infer(default);
+                    if let Some(annotation) = &param.annotation {
+                        self.expect(annotation, found.as_ref(), default);
+                    }
+                }
+                // Function types cannot express optional parameters.
+                let optional = rest.is_some() || parameters.iter().any(|param| param.default.is_some());
+                let t = parameters
+                    .iter()
+                    .map(|param| param.annotation.clone())
+                    .collect::<Option<_>>()
+                    .zip(ret.clone())
+                    .filter(|_| !optional)
+                    .map(|(parameters, ret)| Type::Function {
+                        parameters,
+                        ret: Box::new(ret),
+                    });
+                let mut scope: HashMap<_, _> = parameters
+                    .iter()
+                    .map(|param| {
+                        let binding = Binding {
+                            t: param.annotation.clone(),
+                            annotated: param.annotation.is_some(),
+                        };
+                        (param.name.clone(), binding)
+                    })
+                    .chain(rest.iter().map(|rest| (rest.clone(), Binding::default())))
+                    .collect();
+                // A declared function can call itself, unless a parameter shadows it.
+                if let Some(name) = name {
+                    let binding = Binding {
+                        t: t.clone(),
+                        annotated: false,
+                    };
+                    scope.entry(name.clone()).or_insert(binding);
+                }
+                self.scopes.push(scope);
+                self.rets.push(ret.clone());
+                let found = self.check_block(body);
+                // The last expression statement is the implicit return value.
+                if let (Some(ret), Some(Statement::Expression(exp))) = (ret, body.last()) {
+                    self.expect(ret, found.as_ref(), exp);
+                }
+                self.rets.pop();
+                self.scopes.pop();
+                t
+            }
+            Expression::Call {
+                function,
+                arguments,
+                named,
+            } => {
+                let function = self.infer(function);
+                let arguments: Vec<_> = arguments.iter().map(|arg| (arg, self.infer(arg))).collect();
+                for (_, arg) in named {
+                    self.infer(arg);
+                }
+                let Some(Type::Function { parameters, ret }) = function else {
+                    return None;
+                };
+                // Function types do not name their parameters.
+                if !named.is_empty() {
+                    return Some(*ret);
+                }
+                if parameters.len() != arguments.len() {
+                    self.errors.push(Error::ArgumentCount {
+                        expected: parameters.len(),
+                        got: arguments.len(),
+                        expression: exp.clone(),
+                    });
+                }
+                for (param, (arg, found)) in parameters.iter().zip(arguments) {
+                    self.expect(param, found.as_ref(), arg);
+                }
+                Some(*ret)
+            }
+            Expression::Match { subject, arms } => {
+                self.infer(subject);
+                for arm in arms {
+                    self.scopes.push(HashMap::new());
+                    self.bind_pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.infer(guard);
+                    }
+                    self.infer(&arm.body);
+                    self.scopes.pop();
+                }
+                None
+            }
+            Expression::Assign { target, operator, value } => {
+                // An annotated target keeps its type, so the value must have it too.
+                let expected = self.infer(target);
+                let mut found = self.infer(value);
+                if let Some(operator) = operator {
+                    found = expected.clone().zip(found).and_then(|(current, found)| binary_type(operator, current, found));
+                }
+                if self.annotated(target) {
+                    if let Some(expected) = &expected {
+                        self.expect(expected, found.as_ref(), value);
+                    }
+                } else {
+                    self.forget(target);
+                }
+                found
+            }
+        }
+    }
+
+    /// Records a mismatch if the type found is known and is not the expected one.
+    fn expect(&mut self, expected: &Type, found: Option<&Type>, exp: &Expression) {
+        if let Some(found) = found {
+            if !compatible(expected, found) {
+                self.errors.push(Error::Mismatch {
+                    expected: expected.clone(),
+                    found: found.clone(),
+                    expression: exp.clone(),
+                });
+            }
+        }
+    }
+
+    fn bind(&mut self, name: &str, binding: Binding) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), binding);
+        }
+    }
+
+    /// Binds the names in the pattern to unknown types.
+    fn bind_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Identifier(name) => self.bind(name, Binding::default()),
+            Pattern::Array { elements, rest } => {
+                for elem in elements {
+                    self.bind_pattern(elem);
+                }
+                if let Some(rest) = rest {
+                    self.bind(rest, Binding::default());
+                }
+            }
+            Pattern::Hash(pairs) => {
+                for (_, value) in pairs {
+                    self.bind_pattern(value);
+                }
+            }
+            Pattern::Wildcard | Pattern::Boolean(_) | Pattern::Integer(_) | Pattern::String(_) => {}
+        }
+    }
+
+    fn binding(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Returns whether the assignment target is, or is an element of, an annotated binding.
+    fn annotated(&self, target: &Expression) -> bool {
+        match target {
+            Expression::Identifier(name) => self.binding(name).is_some_and(|b| b.annotated),
+            Expression::Index { left, .. } => self.annotated(left),
+            _ => false,
+        }
+    }
+
+    /// Forgets the inferred type of the binding assigned to by the target.
+    fn forget(&mut self, target: &Expression) {
+        match target {
+            Expression::Identifier(name) => {
+                if let Some(binding) = self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name)) {
+                    binding.t = None;
+                }
+            }
+            Expression::Index { left, .. } => self.forget(left),
+            _ => {}
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<Type> {
+        self.binding(name).and_then(|b| b.t.clone())
+    }
+}
+
+/// Returns the type shared by all the types, if they are all known and there is at least one.
+fn same_type(types: Vec<Option<Type>>) -> Option<Type> {
+    let mut types = types.into_iter();
+    let first = types.next()??;
+    for t in types {
+        if t? != first {
+            return None;
+        }
+    }
+    Some(first)
+}
+
+/// Returns the type of a binary operation on operands of the given types, if known.
+fn binary_type(operator: &Token, left: Type, right: Type) -> Option<Type> {
+    match operator {
+        Token::Equal | Token::NotEqual | Token::LessThan | Token::GreaterThan => Some(Type::Named(BOOL.to_string())),
+        Token::Plus if is_named(&left, STRING) && is_named(&right, STRING) => Some(left),
+        _ if is_named(&left, INT) && is_named(&right, INT) => Some(left),
+        _ => None,
+    }
+}
+
+fn is_named(t: &Type, name: &str) -> bool {
+    matches!(t, Type::Named(n) if n == name)
+}
+
+/// Returns whether a value of type `found` can be used where `expected` is required.
+fn compatible(expected: &Type, found: &Type) -> bool {
+    match (expected, found) {
+        (Type::Named(name), _) | (_, Type::Named(name)) if ![INT, BOOL, STRING].contains(&name.as_str()) => true,
+        (Type::Named(expected), Type::Named(found)) => expected == found,
+        (Type::Array(expected), Type::Array(found)) => compatible(expected, found),
+        (Type::Hash(expected_key, expected_value), Type::Hash(found_key, found_value)) => {
+            compatible(expected_key, found_key) && compatible(expected_value, found_value)
+        }
+        (
+            Type::Function {
+                parameters: expected_params,
+                ret: expected_ret,
+            },
+            Type::Function {
+                parameters: found_params,
+                ret: found_ret,
+            },
+        ) => {
+            expected_params.len() == found_params.len()
+                && expected_params.iter().zip(found_params).all(|(e, f)| compatible(e, f))
+                && compatible(expected_ret, found_ret)
+        }
+        _ => false,
+    }
+}
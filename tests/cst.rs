@@ -0,0 +1,143 @@
+use std::io;
+use std::rc::Rc;
+
+use monkeylang::cst::{self, NodeKind, SyntaxElement, SyntaxNode};
+use monkeylang::error::Error;
+use monkeylang::lexer::{Lexer, Token};
+use monkeylang::parser::{self, Parser};
+
+const SOURCE: &str = "// Adds two numbers.
+let add = fn(x, y) {
+    x   +   y // Implicit return.
+};
+
+/// Names of the function.
+let names = [\"añadir\",\t\"add\"];
+if (add(1, 2) > 2) { add(3, 4) } else { add(5, 6) }
+while (true) { break; } let end = add(7, 8);
+end";
+
+#[test]
+fn print_source_back() -> Result<(), Error> {
+    let tree = cst::parse(io::Cursor::new(SOURCE))?;
+    assert_eq!(tree.to_string(), SOURCE);
+    assert_eq!(tree.range(), 0..SOURCE.len());
+    Ok(())
+}
+
+#[test]
+fn derive_ast() -> Result<(), parser::Error> {
+    let tree = cst::parse(io::Cursor::new(SOURCE)).expect("source should be parsed");
+    let tokens: Vec<Token> = Lexer::new(io::Cursor::new(SOURCE))
+        .collect::<Result<_, _>>()
+        .expect("source should be lexed");
+    assert_eq!(tree.to_ast()?, Parser::new(tokens.iter()).parse()?);
+    Ok(())
+}
+
+#[test]
+fn split_statements() -> Result<(), Error> {
+    let tree = cst::parse(io::Cursor::new(SOURCE))?;
+    let statements: Vec<String> = children(&tree)
+        .into_iter()
+        .filter(|node| node.kind().is_statement())
+        .map(|node| node.to_string())
+        .collect();
+    assert_eq!(
+        statements,
+        [
+            "let add = fn(x, y) {\n    x   +   y // Implicit return.\n};",
+            "/// Names of the function.\nlet names = [\"añadir\",\t\"add\"];",
+            "if (add(1, 2) > 2) { add(3, 4) } else { add(5, 6) }",
+            "while (true) { break; }",
+            "let end = add(7, 8);",
+            "end",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn locate_nodes() -> Result<(), Error> {
+    let tree = cst::parse(io::Cursor::new(SOURCE))?;
+    let tok = tree
+        .tokens()
+        .into_iter()
+        .find(|tok| tok.text() == "\"add\"")
+        .expect("string token should be in the tree");
+    assert_eq!(&SOURCE[tok.range()], "\"add\"");
+    let literal = tok.parent();
+    assert_eq!(literal.kind(), NodeKind::Literal);
+    let array = literal.parent().expect("literal should be in the array");
+    assert_eq!(array.kind(), NodeKind::ArrayExpr);
+    assert_eq!(&SOURCE[array.range()], "[\"añadir\",\t\"add\"]");
+    assert_eq!(array.parent().map(SyntaxNode::kind), Some(NodeKind::LetStatement));
+    Ok(())
+}
+
+#[test]
+fn nest_constructs() -> Result<(), Error> {
+    let tree = cst::parse(io::Cursor::new(SOURCE))?;
+    let statement = &children(&tree)[0];
+    let kinds: Vec<(NodeKind, String)> = descendants(statement)
+        .into_iter()
+        .map(|node| (node.kind(), node.to_string()))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            (NodeKind::IdentifierPattern, "add".to_string()),
+            (NodeKind::FunctionExpr, "fn(x, y) {\n    x   +   y // Implicit return.\n}".to_string()),
+            (NodeKind::ParameterList, "(x, y)".to_string()),
+            (NodeKind::Parameter, "x".to_string()),
+            (NodeKind::Parameter, "y".to_string()),
+            (NodeKind::Block, "{\n    x   +   y // Implicit return.\n}".to_string()),
+            (NodeKind::ExpressionStatement, "x   +   y".to_string()),
+            (NodeKind::BinaryExpr, "x   +   y".to_string()),
+            (NodeKind::Name, "x".to_string()),
+            (NodeKind::Name, "y".to_string()),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn reject_invalid_syntax() {
+    let err = cst::parse(io::Cursor::new("let x = ;")).expect_err("source should not be parsed");
+    assert!(matches!(err, Error::Parsing(_)), "{err:?}");
+}
+
+#[test]
+fn rename_identifier() -> Result<(), Error> {
+    let tree = cst::parse(io::Cursor::new(SOURCE))?;
+    let renamed = tree.rename("add", "sum");
+    assert_eq!(renamed.to_string(), SOURCE.replace("let add", "let sum").replace("add(", "sum("));
+    // Statements without the identifier are shared with the original tree.
+    let original = children(&tree);
+    let renamed = children(&renamed);
+    assert!(Rc::ptr_eq(original[1].green(), renamed[1].green()));
+    assert!(Rc::ptr_eq(original[3].green(), renamed[3].green()));
+    assert!(!Rc::ptr_eq(original[0].green(), renamed[0].green()));
+    Ok(())
+}
+
+/// Returns the nodes below `node`, in pre-order.
+fn descendants(node: &SyntaxNode) -> Vec<SyntaxNode> {
+    children(node)
+        .into_iter()
+        .flat_map(|child| {
+            let mut nodes = vec![child.clone()];
+            nodes.extend(descendants(&child));
+            nodes
+        })
+        .collect()
+}
+
+fn children(node: &SyntaxNode) -> Vec<SyntaxNode> {
+    node.children()
+        .filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+        .collect()
+}
@@ -1,7 +1,7 @@
 use std::io;
 
 use monkeylang::error::*;
-use monkeylang::lexer::{Lexer, Token};
+use monkeylang::lexer::{self, Lexeme, LexemeKind, Lexer, Position, Span, Token};
 
 #[test]
 fn skip_whitespaces() {
@@ -13,19 +13,23 @@ fn skip_whitespaces() {
 
 #[test]
 fn detect_single_chars_only() -> Result<(), Error> {
-    const CHARS: &str = "=*!,>{(<-+});/";
+    const CHARS: &str = "=*!|:,>{[(<-+}]);/";
     const TOKENS: &[Token] = &[
         Token::Assign,
         Token::Asterisk,
         Token::Bang,
+        Token::Bar,
+        Token::Colon,
         Token::Comma,
         Token::GreaterThan,
         Token::LeftBrace,
+        Token::LeftBracket,
         Token::LeftParen,
         Token::LessThan,
         Token::Minus,
         Token::Plus,
         Token::RightBrace,
+        Token::RightBracket,
         Token::RightParen,
         Token::Semicolon,
         Token::Slash,
@@ -42,8 +46,20 @@ fn detect_single_chars_only() -> Result<(), Error> {
 
 #[test]
 fn detect_two_chars_only() -> Result<(), Error> {
-    const CHARS: &str = "==!=";
-    const TOKENS: &[Token] = &[Token::Equal, Token::NotEqual];
+    const CHARS: &str = "==!=+=-=*=/=&&|||>=>->";
+    const TOKENS: &[Token] = &[
+        Token::Equal,
+        Token::NotEqual,
+        Token::PlusAssign,
+        Token::MinusAssign,
+        Token::AsteriskAssign,
+        Token::SlashAssign,
+        Token::And,
+        Token::Or,
+        Token::Pipe,
+        Token::FatArrow,
+        Token::Arrow,
+    ];
 
     let lex = Lexer::new(io::Cursor::new(CHARS));
     for (i, tok) in lex.into_iter().enumerate() {
@@ -56,7 +72,7 @@ fn detect_two_chars_only() -> Result<(), Error> {
 
 #[test]
 fn detect_reserved_words_only() -> Result<(), Error> {
-    const CHARS: &str = "else false fn if let return false";
+    const CHARS: &str = "else false fn if let return true";
     const TOKENS: &[Token] = &[
         Token::Else,
         Token::False,
@@ -76,6 +92,26 @@ fn detect_reserved_words_only() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn detect_loop_reserved_words() -> Result<(), Error> {
+    const CHARS: &str = "while for in break continue;";
+    const TOKENS: &[Token] = &[
+        Token::While,
+        Token::For,
+        Token::In,
+        Token::Break,
+        Token::Continue,
+        Token::Semicolon,
+    ];
+
+    let lex = Lexer::new(io::Cursor::new(CHARS));
+    for (i, tok) in lex.into_iter().enumerate() {
+        let tok = tok?;
+        assert_eq!(tok, TOKENS[i]);
+    }
+    Ok(())
+}
+
 #[test]
 fn detect_integer_assignments() -> Result<(), Error> {
     const CHARS: &str = "\
@@ -147,3 +183,137 @@ let result = add(five, ten);";
     }
     Ok(())
 }
+
+#[test]
+fn detect_ellipsis() -> Result<(), Error> {
+    const CHARS: &str = "[...rest]";
+    let tokens = &[
+        Token::LeftBracket,
+        Token::Ellipsis,
+        Token::Identifier("rest".to_string()),
+        Token::RightBracket,
+    ];
+
+    let lex = Lexer::new(io::Cursor::new(CHARS));
+    for (i, tok) in lex.into_iter().enumerate() {
+        let tok = tok?;
+        assert_eq!(tok, tokens[i]);
+    }
+    Ok(())
+}
+
+#[test]
+fn detect_invalid_dots() {
+    for src in [".", "..", "....", "x = ..;"] {
+        let result: Result<Vec<Token>, _> = Lexer::new(io::Cursor::new(src)).collect();
+        assert!(matches!(result, Err(lexer::Error::Token(_))), "{}", src);
+    }
+}
+
+#[test]
+fn detect_strings() -> Result<(), Error> {
+    const CHARS: &str = r#"let name = "monkey lang";"#;
+    let tokens = &[
+        Token::Let,
+        Token::Identifier("name".to_string()),
+        Token::Assign,
+        Token::String("monkey lang".to_string()),
+        Token::Semicolon,
+    ];
+
+    let lex = Lexer::new(io::Cursor::new(CHARS));
+    for (i, tok) in lex.into_iter().enumerate() {
+        let tok = tok?;
+        assert_eq!(tok, tokens[i]);
+    }
+    Ok(())
+}
+
+#[test]
+fn detect_unterminated_string() {
+    let mut lex = Lexer::new(io::Cursor::new(r#""monkey"#));
+    assert!(matches!(lex.next(), Some(Err(lexer::Error::Token(_)))));
+}
+
+#[test]
+fn detect_doc_comments() -> Result<(), Error> {
+    const CHARS: &str = "/// Adds.\n///\n///  Indented.\n//// Not a doc comment.\n// Neither.\nx";
+    let tokens = &[
+        Token::DocComment("Adds.".to_string()),
+        Token::DocComment("".to_string()),
+        Token::DocComment(" Indented.".to_string()),
+        Token::Identifier("x".to_string()),
+    ];
+
+    let lex = Lexer::new(io::Cursor::new(CHARS));
+    let lexed: Vec<Token> = lex.into_iter().collect::<Result<_, _>>()?;
+    assert_eq!(lexed, tokens);
+    Ok(())
+}
+
+#[test]
+fn skip_comments() -> Result<(), Error> {
+    const CHARS: &str = "// A comment.\nx / y // Another one.";
+    let tokens = &[
+        Token::Identifier("x".to_string()),
+        Token::Slash,
+        Token::Identifier("y".to_string()),
+    ];
+
+    let lex = Lexer::new(io::Cursor::new(CHARS));
+    let lexed: Vec<Token> = lex.into_iter().collect::<Result<_, _>>()?;
+    assert_eq!(lexed, tokens);
+    Ok(())
+}
+
+#[test]
+fn detect_last_token_at_end_of_source() -> Result<(), Error> {
+    let lex = Lexer::new(io::Cursor::new("x != 10"));
+    let lexed: Vec<Token> = lex.into_iter().collect::<Result<_, _>>()?;
+    assert_eq!(lexed, [Token::Identifier("x".to_string()), Token::NotEqual, Token::Integer(10)]);
+    Ok(())
+}
+
+#[test]
+fn detect_unicode_strings() -> Result<(), Error> {
+    let mut lex = Lexer::new(io::Cursor::new("\"añadir 🐒\""));
+    assert_eq!(lex.next_token()?, Token::String("añadir 🐒".to_string()));
+    Ok(())
+}
+
+#[test]
+fn detect_lexemes() -> Result<(), Error> {
+    const CHARS: &str = "let é = 1; // Ok.\n  x";
+    let lexemes: Vec<Lexeme> = Lexer::new(io::Cursor::new(CHARS))
+        .lexemes()
+        .collect::<Result<_, _>>()?;
+    let texts: Vec<&str> = lexemes.iter().map(|lexeme| lexeme.text.as_str()).collect();
+    assert_eq!(texts.concat(), CHARS);
+    assert_eq!(
+        lexemes[2],
+        Lexeme {
+            kind: LexemeKind::Token(Token::Identifier("é".to_string())),
+            text: "é".to_string(),
+            span: Span {
+                start: Position {
+                    offset: 4,
+                    line: 1,
+                    column: 5
+                },
+                end: Position {
+                    offset: 6,
+                    line: 1,
+                    column: 6
+                },
+            },
+        }
+    );
+    let comment = &lexemes[lexemes.len() - 3];
+    assert_eq!(comment.kind, LexemeKind::Comment);
+    assert_eq!(comment.text, "// Ok.");
+    let last = lexemes.last().unwrap();
+    assert_eq!(last.kind, LexemeKind::Token(Token::Identifier("x".to_string())));
+    assert_eq!(last.span.start.line, 2);
+    assert_eq!(last.span.start.column, 3);
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use std::io;
+
+use monkeylang::lexer::{Lexer, Token};
+use monkeylang::macros::{self, expand_macros};
+use monkeylang::parser::*;
+
+#[test]
+fn expand_unless_macro() -> Result<(), macros::Error> {
+    let mut ast = parse(
+        r#"
+let unless = macro(cond, cons, alt) {
+    quote(if (!(unquote(cond))) { unquote(cons); } else { unquote(alt); });
+};
+unless(10 > 5, puts("not greater"), puts("greater"));
+"#,
+    );
+    expand_macros(&mut ast)?;
+    let expected = parse(r#"if (!(10 > 5)) { puts("not greater"); } else { puts("greater"); }"#);
+    assert_eq!(ast, expected);
+    Ok(())
+}
+
+#[test]
+fn expand_nested_macro_calls() -> Result<(), macros::Error> {
+    let mut ast = parse(
+        "
+let twice = macro(x) { quote(unquote(x) + unquote(x)); };
+let y = [twice(twice(1))];
+",
+    );
+    expand_macros(&mut ast)?;
+    let expected = parse("let y = [(1 + 1) + (1 + 1)];");
+    assert_eq!(ast, expected);
+    Ok(())
+}
+
+#[test]
+fn expand_evaluated_macro_bodies() -> Result<(), macros::Error> {
+    let tests = vec![
+        (
+            "let cube = macro(x) { let code = quote(1); let i = 0; while (i < 3) { code = quote(unquote(code) * unquote(x)); i += 1; } code }; cube(a);",
+            "1 * a * a * a;",
+        ),
+        ("let m = macro(x) { x; }; m(f(1));", "f(1);"),
+        ("let m = macro() { quote(unquote(1 + 1) * unquote([\"a\", true])); }; m();", "2 * [\"a\", true];"),
+        (
+            "let pick = macro(first, a, b) { if (first == quote(true)) { return a; } b }; pick(true, x, y); pick(false, x, y);",
+            "x; y;",
+        ),
+    ];
+    for (src, expanded) in tests {
+        let mut ast = parse(src);
+        expand_macros(&mut ast)?;
+        assert_eq!(ast, parse(expanded), "{}", src);
+    }
+    Ok(())
+}
+
+#[test]
+fn expand_macros_until_none_is_left() -> Result<(), macros::Error> {
+    let mut ast = parse(
+        "
+let double = macro(x) { quote(unquote(x) * 2); };
+let quadruple = macro(x) { quote(double(double(unquote(x)))); };
+quadruple(y);
+",
+    );
+    expand_macros(&mut ast)?;
+    assert_eq!(ast, parse("y * 2 * 2;"));
+
+    let mut ast = parse("let forever = macro(x) { quote(forever(unquote(x))); }; forever(1);");
+    assert!(matches!(
+        expand_macros(&mut ast),
+        Err(macros::Error::TooDeep { name, limit: macros::EXPANSION_LIMIT }) if name == "forever"
+    ));
+    Ok(())
+}
+
+#[test]
+fn expand_scoped_macros() -> Result<(), macros::Error> {
+    let mut ast = parse(
+        "
+let f = fn(x) {
+    let twice = macro(x) { quote(unquote(x) + unquote(x)); };
+    twice(x)
+};
+twice(1);
+",
+    );
+    expand_macros(&mut ast)?;
+    assert_eq!(ast, parse("let f = fn(x) { x + x }; twice(1);"));
+    Ok(())
+}
+
+#[test]
+fn expand_exported_and_shadowed_macros() -> Result<(), macros::Error> {
+    let mut ast = parse(
+        "
+export let twice = macro(x) { quote(unquote(x) + unquote(x)); };
+let a = twice(1);
+let twice = fn(x) { x * 2 };
+let b = twice(2);
+let f = fn() { let [twice] = [a]; twice(3) };
+",
+    );
+    expand_macros(&mut ast)?;
+    let expected = parse(
+        "
+let a = 1 + 1;
+let twice = fn(x) { x * 2 };
+let b = twice(2);
+let f = fn() { let [twice] = [a]; twice(3) };
+",
+    );
+    assert_eq!(ast, expected);
+    Ok(())
+}
+
+#[test]
+fn stop_endless_macro_bodies() {
+    let mut ast = parse("let m = macro() { while (true) {} quote(1) }; m();");
+    assert!(matches!(
+        expand_macros(&mut ast),
+        Err(macros::Error::TooManySteps { limit: macros::STEP_LIMIT })
+    ));
+}
+
+#[test]
+fn expand_bad_macro_calls() {
+    let tests = vec![
+        "let m = macro(x) { quote(x); }; m(1, 2);",
+        "let m = macro(x) { 1; }; m(1);",
+        "let m = macro(x) { quote(unquote(y)); }; m(1);",
+        "let m = macro(x) { quote(unquote(x, x)); }; m(1);",
+        "let m = macro(x) { quote(unquote(if (false) { 1 })); }; m(1);",
+        "let m = macro(x) { quote(unquote(x + 1)); }; m(1);",
+        "let m = macro(x) { fn() { x }; }; m(1);",
+        "let m = macro(x) { quote(x); }; m(x: 1);",
+        "f(macro(x) { quote(x); });",
+    ];
+    for test in tests {
+        let mut ast = parse(test);
+        assert!(expand_macros(&mut ast).is_err(), "{}", test);
+    }
+}
+
+fn parse(src: &str) -> AST {
+    let tokens: Vec<Token> = Lexer::new(io::Cursor::new(src))
+        .collect::<Result<_, _>>()
+        .expect("source should be lexed");
+    Parser::new(tokens.iter()).parse().expect("source should be parsed")
+}
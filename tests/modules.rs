@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use monkeylang::modules::{self, Loader};
+use monkeylang::parser::Expression;
+
+#[test]
+fn load_module_with_imports() -> Result<(), modules::Error> {
+    let dir = SourceDir::new(
+        "imports",
+        &[
+            ("main.monkey", r#"import "lib/math.monkey" as math; import "lib/util.monkey" as util;"#),
+            ("lib/math.monkey", r#"import "util.monkey" as util; export let two = 2; let hidden = 3;"#),
+            ("lib/util.monkey", r#"export let name = "util";"#),
+        ],
+    );
+    let mut loader = Loader::new();
+    let main = loader.load(dir.join("main.monkey"))?;
+
+    let math = &main.imports()["math"];
+    assert_eq!(math.exports().len(), 1);
+    assert_eq!(math.exports()["two"], Expression::Integer(2));
+    let util = &main.imports()["util"];
+    assert_eq!(util.exports()["name"], Expression::String("util".to_string()));
+    // Both imports of util resolve to the same file, which is loaded once.
+    assert!(Rc::ptr_eq(util, &math.imports()["util"]));
+    Ok(())
+}
+
+#[test]
+fn load_import_cycle() {
+    let dir = SourceDir::new(
+        "cycle",
+        &[
+            ("a.monkey", r#"import "b.monkey" as b;"#),
+            ("b.monkey", r#"import "c.monkey" as c;"#),
+            ("c.monkey", r#"import "b.monkey" as b;"#),
+        ],
+    );
+    let err = Loader::new().load(dir.join("a.monkey")).unwrap_err();
+    let modules::Error::Cycle(chain) = &err else {
+        panic!("expected an import cycle, got {:?}", err);
+    };
+    let names: Vec<_> = chain.iter().map(|path| path.file_name().unwrap()).collect();
+    assert_eq!(names, ["b.monkey", "c.monkey", "b.monkey"]);
+    assert!(err.to_string().contains("b.monkey -> "));
+}
+
+#[test]
+fn load_missing_module() {
+    let dir = SourceDir::new("missing", &[("main.monkey", r#"import "nowhere.monkey" as n;"#)]);
+    let err = Loader::new().load(dir.join("main.monkey"));
+    assert!(err.is_err_and(|e| matches!(
+        e,
+        modules::Error::Import { source, .. } if matches!(*source, modules::Error::IO { .. })
+    )));
+}
+
+#[test]
+fn load_missing_nested_module() {
+    let dir = SourceDir::new(
+        "nested",
+        &[
+            ("main.monkey", r#"import "a.monkey" as a;"#),
+            ("a.monkey", r#"import "nowhere.monkey" as n;"#),
+        ],
+    );
+    let err = Loader::new().load(dir.join("main.monkey")).unwrap_err();
+    let modules::Error::Import { chain, source } = &err else {
+        panic!("expected an error in an import, got {:?}", err);
+    };
+    let names: Vec<_> = chain.iter().map(|path| path.file_name().unwrap()).collect();
+    assert_eq!(names, ["main.monkey", "a.monkey"]);
+    assert!(matches!(source.as_ref(), modules::Error::IO { path, .. } if path.ends_with("nowhere.monkey")));
+}
+
+#[test]
+fn load_duplicate_names() {
+    let dir = SourceDir::new(
+        "duplicates",
+        &[
+            ("imports.monkey", r#"import "lib.monkey" as lib; import "other.monkey" as lib;"#),
+            ("exports.monkey", "export let x = 1; export fn x() { 2 }"),
+            ("lib.monkey", ""),
+            ("other.monkey", ""),
+        ],
+    );
+    let err = Loader::new().load(dir.join("imports.monkey"));
+    assert!(err.is_err_and(|e| matches!(e, modules::Error::DuplicateImport { alias, .. } if alias == "lib")));
+    let err = Loader::new().load(dir.join("exports.monkey"));
+    assert!(err.is_err_and(|e| matches!(e, modules::Error::DuplicateExport { name, .. } if name == "x")));
+}
+
+/// A directory of source files for a single test, removed when dropped.
+struct SourceDir(PathBuf);
+
+impl SourceDir {
+    /// Writes the source files in a new directory for the test.
+    fn new(test: &str, files: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("monkeylang-modules-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (name, source) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, format!("{}\n", source)).unwrap();
+        }
+        Self(dir)
+    }
+
+    fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for SourceDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
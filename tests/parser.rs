@@ -2,10 +2,13 @@ use monkeylang::lexer::*;
 use monkeylang::parser::{self, *};
 
 #[test]
+#[allow(clippy::useless_vec)] // The token list is written like the test tables.
 fn parse_let_statement() -> Result<(), parser::Error> {
     let expected = AST::new(vec![Statement::Let {
-        identifier: "x".to_string(),
-        expression: Expression::None,
+        pattern: Pattern::Identifier("x".to_string()),
+        annotation: None,
+        expression: Expression::Integer(5),
+        doc: None,
     }]);
     let ast = Parser::new(
         vec![
@@ -23,6 +26,7 @@ fn parse_let_statement() -> Result<(), parser::Error> {
 }
 
 #[test]
+#[allow(clippy::useless_vec)] // The token list is written like the test tables.
 fn parse_bad_let_statement() {
     let ast = Parser::new(
         vec![
@@ -35,12 +39,201 @@ fn parse_bad_let_statement() {
         .iter(),
     )
     .parse();
-    assert!(ast.is_err_and(|e| { matches!(e, parser::Error::ExpectedToken { expected: _, got: _ }) }));
+    assert!(ast.is_err_and(|e| { matches!(e, parser::Error::ExpectedToken(_)) }));
 }
 
 #[test]
+fn parse_destructuring_let_statements() -> Result<(), parser::Error> {
+    let tests = vec![
+        (
+            vec![
+                Token::Let,
+                Token::LeftBracket,
+                Token::Identifier("a".to_string()),
+                Token::Comma,
+                Token::Identifier("b".to_string()),
+                Token::Comma,
+                Token::Ellipsis,
+                Token::Identifier("rest".to_string()),
+                Token::RightBracket,
+                Token::Assign,
+                Token::Identifier("xs".to_string()),
+                Token::Semicolon,
+            ], // let [a, b, ...rest] = xs;.
+            AST::new(vec![Statement::Let {
+                pattern: Pattern::Array {
+                    elements: vec![Pattern::Identifier("a".to_string()), Pattern::Identifier("b".to_string())],
+                    rest: Some("rest".to_string()),
+                },
+                annotation: None,
+                expression: Expression::Identifier("xs".to_string()),
+                doc: None,
+            }]),
+        ),
+        (
+            vec![
+                Token::Let,
+                Token::LeftBrace,
+                Token::Identifier("name".to_string()),
+                Token::Comma,
+                Token::String("age".to_string()),
+                Token::Colon,
+                Token::LeftBracket,
+                Token::Identifier("_".to_string()),
+                Token::RightBracket,
+                Token::RightBrace,
+                Token::Assign,
+                Token::Identifier("person".to_string()),
+                Token::Semicolon,
+            ], // let {name, "age": [_]} = person;.
+            AST::new(vec![Statement::Let {
+                pattern: Pattern::Hash(vec![
+                    (
+                        Expression::String("name".to_string()),
+                        Pattern::Identifier("name".to_string()),
+                    ),
+                    (
+                        Expression::String("age".to_string()),
+                        Pattern::Array {
+                            elements: vec![Pattern::Wildcard],
+                            rest: None,
+                        },
+                    ),
+                ]),
+                annotation: None,
+                expression: Expression::Identifier("person".to_string()),
+                doc: None,
+            }]),
+        ),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_misplaced_rest_pattern() {
+    let ast = Parser::new(
+        [
+            Token::Let,
+            Token::LeftBracket,
+            Token::Ellipsis,
+            Token::Identifier("rest".to_string()),
+            Token::Comma,
+            Token::Identifier("a".to_string()),
+            Token::RightBracket,
+            Token::Assign,
+            Token::Identifier("xs".to_string()),
+            Token::Semicolon,
+        ]
+        .iter(),
+    )
+    .parse(); // let [...rest, a] = xs;.
+    assert!(ast.is_err_and(|e| {
+        matches!(
+            e,
+            parser::Error::ExpectedToken(mismatch)
+                if mismatch.expected == [Token::RightBracket]
+                    && matches!(mismatch.found, Some(Token::Identifier(_)))
+        )
+    }));
+}
+
+#[test]
+fn parse_import_export_statements() -> Result<(), parser::Error> {
+    let tests = vec![(
+        vec![
+            Token::Import,
+            Token::String("lib.monkey".to_string()),
+            Token::As,
+            Token::Identifier("lib".to_string()),
+            Token::Semicolon,
+            Token::Export,
+            Token::Let,
+            Token::Identifier("x".to_string()),
+            Token::Assign,
+            Token::Integer(1),
+            Token::Semicolon,
+        ], // import "lib.monkey" as lib; export let x = 1;.
+        AST::new(vec![
+            Statement::Import {
+                path: "lib.monkey".to_string(),
+                alias: "lib".to_string(),
+            },
+            Statement::Export(boxx(Statement::Let {
+                pattern: Pattern::Identifier("x".to_string()),
+                annotation: None,
+                expression: Expression::Integer(1),
+                doc: None,
+            })),
+        ]),
+    )];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_literal_let_patterns() {
+    let tests = [
+        "let 5 = x;",
+        "let -1 = x;",
+        "let [a, \"b\"] = xs;",
+        "let {\"ok\": true} = result;",
+    ];
+    for src in tests {
+        let err = parse_source(src).expect_err(src);
+        assert!(matches!(err, parser::Error::LiteralPattern { .. }), "{}: {}", src, err);
+    }
+    // Patterns that only destructure and bind are still allowed.
+    parse_source("let [_, {a, \"b\": [c, ...d]}] = xs;").expect("destructuring should parse");
+}
+
+#[test]
+fn parse_nested_imports_and_exports() {
+    let tests = [
+        ("if (x) { import \"a\" as b; }", Token::Import),
+        ("fn() { export let y = 1; }", Token::Export),
+        ("while (true) { /// Doc.\nexport fn f() {} }", Token::Export),
+        ("for (x in xs) { import \"a\" as b }", Token::Import),
+    ];
+    for (src, token) in tests {
+        let err = parse_source(src).expect_err(src);
+        assert!(matches!(&err, parser::Error::NotTopLevel { token: t, .. } if *t == token), "{}: {}", src, err);
+    }
+}
+
+#[test]
+fn parse_bad_export_statement() {
+    let ast = Parser::new(
+        [
+            Token::Export,
+            Token::Let,
+            Token::LeftBracket,
+            Token::Identifier("x".to_string()),
+            Token::RightBracket,
+            Token::Assign,
+            Token::Identifier("xs".to_string()),
+            Token::Semicolon,
+        ]
+        .iter(),
+    )
+    .parse(); // export let [x] = xs;.
+    assert!(ast.is_err_and(|e| matches!(e, parser::Error::ExportPattern { .. })));
+}
+
+#[test]
+#[allow(clippy::useless_vec)] // The token list is written like the test tables.
 fn parse_return_statement() -> Result<(), parser::Error> {
-    let expected = AST::new(vec![Statement::Return(Expression::None)]);
+    let expected = AST::new(vec![Statement::Return(Expression::Binary {
+        left: boxx(Expression::Integer(5)),
+        operator: Token::Plus,
+        right: boxx(Expression::Integer(10)),
+    })]);
     let ast = Parser::new(
         vec![
             Token::Return,
@@ -56,6 +249,34 @@ fn parse_return_statement() -> Result<(), parser::Error> {
     Ok(())
 }
 
+#[test]
+fn parse_empty_return_statements() -> Result<(), parser::Error> {
+    let tests = vec![
+        (vec![Token::Return, Token::Semicolon], vec![Statement::Return(Expression::None)]), // return;
+        (vec![Token::Return], vec![Statement::Return(Expression::None)]),                   // return
+        (
+            vec![
+                Token::If,
+                Token::LeftParen,
+                Token::True,
+                Token::RightParen,
+                Token::LeftBrace,
+                Token::Return,
+                Token::RightBrace,
+            ], // if (true) { return }
+            vec![Statement::Expression(Expression::If {
+                cond: boxx(Expression::Boolean(true)),
+                conseq: vec![Statement::Return(Expression::None)],
+                altern: None,
+            })],
+        ),
+    ];
+    for (tokens, statements) in tests {
+        assert_eq!(Parser::new(tokens.iter()).parse()?, AST::new(statements));
+    }
+    Ok(())
+}
+
 #[test]
 fn parse_literal_expressions() -> Result<(), parser::Error> {
     let tests = vec![
@@ -294,6 +515,1528 @@ fn parse_if_else() -> Result<(), parser::Error> {
     Ok(())
 }
 
+#[test]
+fn parse_array_literals() -> Result<(), parser::Error> {
+    let tests = vec![
+        (
+            vec![Token::LeftBracket, Token::RightBracket], // [].
+            AST::new(vec![Statement::Expression(Expression::Array(vec![]))]),
+        ),
+        (
+            vec![
+                Token::LeftBracket,
+                Token::Integer(1),
+                Token::Comma,
+                Token::Integer(2),
+                Token::Asterisk,
+                Token::Integer(2),
+                Token::RightBracket,
+            ], // [1, 2 * 2].
+            AST::new(vec![Statement::Expression(Expression::Array(vec![
+                Expression::Integer(1),
+                Expression::Binary {
+                    left: boxx(Expression::Integer(2)),
+                    operator: Token::Asterisk,
+                    right: boxx(Expression::Integer(2)),
+                },
+            ]))]),
+        ),
+        (
+            vec![
+                Token::LeftBracket,
+                Token::True,
+                Token::Comma,
+                Token::RightBracket,
+            ], // [true,].
+            AST::new(vec![Statement::Expression(Expression::Array(vec![Expression::Boolean(true)]))]),
+        ),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_bad_array_literal() {
+    let ast = Parser::new(
+        [
+            Token::LeftBracket,
+            Token::Integer(1),
+            Token::Integer(2),
+            Token::RightBracket,
+        ]
+        .iter(),
+    )
+    .parse(); // [1 2].
+    assert!(ast.is_err_and(|e| {
+        matches!(
+            e,
+            parser::Error::ExpectedToken(mismatch)
+                if mismatch.expected == [Token::RightBracket, Token::Comma]
+                    && mismatch.found == Some(Token::Integer(2))
+        )
+    }));
+}
+
+#[test]
+fn parse_index_expressions() -> Result<(), parser::Error> {
+    let tests = vec![
+        (
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::LeftBracket,
+                Token::Integer(1),
+                Token::Plus,
+                Token::Integer(1),
+                Token::RightBracket,
+            ], // a[1 + 1].
+            AST::new(vec![Statement::Expression(Expression::Index {
+                left: boxx(Expression::Identifier("a".to_string())),
+                index: boxx(Expression::Binary {
+                    left: boxx(Expression::Integer(1)),
+                    operator: Token::Plus,
+                    right: boxx(Expression::Integer(1)),
+                }),
+            })]),
+        ),
+        (
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::LeftBracket,
+                Token::Integer(0),
+                Token::RightBracket,
+                Token::LeftBracket,
+                Token::Integer(1),
+                Token::RightBracket,
+            ], // a[0][1].
+            AST::new(vec![Statement::Expression(Expression::Index {
+                left: boxx(Expression::Index {
+                    left: boxx(Expression::Identifier("a".to_string())),
+                    index: boxx(Expression::Integer(0)),
+                }),
+                index: boxx(Expression::Integer(1)),
+            })]),
+        ),
+        (
+            vec![
+                Token::Minus,
+                Token::Identifier("a".to_string()),
+                Token::LeftBracket,
+                Token::Integer(0),
+                Token::RightBracket,
+                Token::Asterisk,
+                Token::Integer(2),
+            ], // -a[0] * 2.
+            AST::new(vec![Statement::Expression(Expression::Binary {
+                left: boxx(Expression::Unary {
+                    operator: Token::Minus,
+                    expression: boxx(Expression::Index {
+                        left: boxx(Expression::Identifier("a".to_string())),
+                        index: boxx(Expression::Integer(0)),
+                    }),
+                }),
+                operator: Token::Asterisk,
+                right: boxx(Expression::Integer(2)),
+            })]),
+        ),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_if_else_blocks() -> Result<(), parser::Error> {
+    let tests = vec![(
+        vec![
+            Token::If,
+            Token::LeftParen,
+            Token::Identifier("x".to_string()),
+            Token::RightParen,
+            Token::LeftBrace,
+            Token::Integer(1),
+            Token::RightBrace,
+            Token::Else,
+            Token::LeftBrace,
+            Token::If,
+            Token::LeftParen,
+            Token::True,
+            Token::RightParen,
+            Token::LeftBrace,
+            Token::RightBrace,
+            Token::RightBrace,
+        ], // if (x) { 1 } else { if (true) {} }.
+        AST::new(vec![Statement::Expression(Expression::If {
+            cond: boxx(Expression::Identifier("x".to_string())),
+            conseq: vec![Statement::Expression(Expression::Integer(1))],
+            altern: Some(vec![Statement::Expression(Expression::If {
+                cond: boxx(Expression::Boolean(true)),
+                conseq: vec![],
+                altern: None,
+            })]),
+        })]),
+    )];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_unbalanced_braces() {
+    let ast = Parser::new([Token::Integer(1), Token::RightBrace].iter()).parse(); // 1 }.
+    assert!(ast.is_err_and(|e| matches!(
+        e,
+        parser::Error::ExpectedToken(mismatch) if mismatch.found == Some(Token::RightBrace)
+    )));
+    let Err(parser::Error::ExpectedToken(mismatch)) = parse_source("let x = 1; }") else {
+        panic!("the stray brace should be unexpected");
+    };
+    assert!(mismatch.expected.contains(&Token::Let));
+    assert_eq!(mismatch.span.map(|span| (span.start.line, span.start.column)), Some((1, 12)));
+}
+
+#[test]
+fn parse_function_literals() -> Result<(), parser::Error> {
+    let tests = vec![
+        (
+            vec![
+                Token::Function,
+                Token::LeftParen,
+                Token::RightParen,
+                Token::LeftBrace,
+                Token::RightBrace,
+            ], // fn() {}.
+            AST::new(vec![Statement::Expression(Expression::Function {
+                name: None,
+                parameters: vec![],
+                rest: None,
+                ret: None,
+                body: vec![],
+            })]),
+        ),
+        (
+            vec![
+                Token::Function,
+                Token::LeftParen,
+                Token::Identifier("x".to_string()),
+                Token::Comma,
+                Token::Identifier("y".to_string()),
+                Token::RightParen,
+                Token::LeftBrace,
+                Token::Return,
+                Token::Identifier("x".to_string()),
+                Token::Plus,
+                Token::Identifier("y".to_string()),
+                Token::Semicolon,
+                Token::RightBrace,
+            ], // fn(x, y) { return x + y; }.
+            AST::new(vec![Statement::Expression(Expression::Function {
+                name: None,
+                parameters: vec![
+                    Parameter {
+                        name: "x".to_string(),
+                        annotation: None,
+                        default: None,
+                    },
+                    Parameter {
+                        name: "y".to_string(),
+                        annotation: None,
+                        default: None,
+                    },
+                ],
+                rest: None,
+                ret: None,
+                body: vec![Statement::Return(Expression::Binary {
+                    left: boxx(Expression::Identifier("x".to_string())),
+                    operator: Token::Plus,
+                    right: boxx(Expression::Identifier("y".to_string())),
+                })],
+            })]),
+        ),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_function_declarations() -> Result<(), parser::Error> {
+    let id = |name: Option<&str>| Expression::Function {
+        name: name.map(str::to_string),
+        parameters: vec![Parameter {
+            name: "x".to_string(),
+            annotation: None,
+            default: None,
+        }],
+        rest: None,
+        ret: None,
+        body: vec![Statement::Expression(Expression::Identifier("x".to_string()))],
+    };
+    let tests = vec![
+        (
+            vec![
+                Token::Function,
+                Token::Identifier("id".to_string()),
+                Token::LeftParen,
+                Token::Identifier("x".to_string()),
+                Token::RightParen,
+                Token::LeftBrace,
+                Token::Identifier("x".to_string()),
+                Token::RightBrace,
+                Token::Function,
+                Token::LeftParen,
+                Token::Identifier("x".to_string()),
+                Token::RightParen,
+                Token::LeftBrace,
+                Token::Identifier("x".to_string()),
+                Token::RightBrace,
+                Token::LeftParen,
+                Token::Integer(1),
+                Token::RightParen,
+                Token::Semicolon,
+            ], // fn id(x) { x } fn(x) { x }(1);.
+            AST::new(vec![
+                Statement::Let {
+                    pattern: Pattern::Identifier("id".to_string()),
+                    annotation: None,
+                    expression: id(Some("id")),
+                    doc: None,
+                },
+                Statement::Expression(Expression::Call {
+                    function: boxx(id(None)),
+                    arguments: vec![Expression::Integer(1)],
+                    named: vec![],
+                }),
+            ]),
+        ),
+        (
+            vec![
+                Token::DocComment("Returns x.".to_string()),
+                Token::Export,
+                Token::Function,
+                Token::Identifier("id".to_string()),
+                Token::LeftParen,
+                Token::Identifier("x".to_string()),
+                Token::RightParen,
+                Token::LeftBrace,
+                Token::Identifier("x".to_string()),
+                Token::RightBrace,
+            ], // /// Returns x.\nexport fn id(x) { x }.
+            AST::new(vec![Statement::Export(boxx(Statement::Let {
+                pattern: Pattern::Identifier("id".to_string()),
+                annotation: None,
+                expression: id(Some("id")),
+                doc: Some("Returns x.".to_string()),
+            }))]),
+        ),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_bad_function_declaration() {
+    let tokens = [Token::Export, Token::Function, Token::LeftParen, Token::RightParen]; // export fn().
+    let ast = Parser::new(tokens.iter()).parse();
+    assert!(ast.is_err_and(|e| matches!(e, parser::Error::ExpectedToken(_))));
+}
+
+#[test]
+fn parse_optional_parameters() -> Result<(), parser::Error> {
+    let tests = vec![
+        (
+            "fn(x, y = 10, ...rest) { x }",
+            Expression::Function {
+                name: None,
+                parameters: vec![
+                    Parameter {
+                        name: "x".to_string(),
+                        annotation: None,
+                        default: None,
+                    },
+                    Parameter {
+                        name: "y".to_string(),
+                        annotation: None,
+                        default: Some(Expression::Integer(10)),
+                    },
+                ],
+                rest: Some("rest".to_string()),
+                ret: None,
+                body: vec![Statement::Expression(Expression::Identifier("x".to_string()))],
+            },
+        ),
+        (
+            "f(x + 1, y: x)",
+            Expression::Call {
+                function: boxx(Expression::Identifier("f".to_string())),
+                arguments: vec![Expression::Binary {
+                    left: boxx(Expression::Identifier("x".to_string())),
+                    operator: Token::Plus,
+                    right: boxx(Expression::Integer(1)),
+                }],
+                named: vec![("y".to_string(), Expression::Identifier("x".to_string()))],
+            },
+        ),
+    ];
+    for (src, expected) in tests {
+        assert_eq!(parse_source(src)?, AST::new(vec![Statement::Expression(expected)]));
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_bad_parameters() {
+    let tests = [
+        ("fn(x = 1, y) {}", "parameter y has no default value but follows one that has"),
+        ("fn(...rest, x) {}", "parameter x follows the rest parameter"),
+        ("fn(x, x) {}", "duplicate parameter x"),
+        ("fn(x, ...x) {}", "duplicate parameter x"),
+        ("f(y: 1, 2)", "positional argument (2) follows named arguments"),
+        ("f(y: 1, y: 2)", "argument y is passed more than once"),
+    ];
+    for (src, message) in tests {
+        let err = parse_source(src).expect_err(src);
+        assert_eq!(err.to_string(), message, "{}", src);
+    }
+}
+
+#[test]
+fn parse_lambdas() -> Result<(), parser::Error> {
+    let tests = [
+        ("xs |> map(|x| x * 2)", "xs |> map(fn(x) { x * 2 })"),
+        ("|a, b| { a + b }", "fn(a, b) { a + b }"),
+        ("|x = 1, ...rest| rest", "fn(x = 1, ...rest) { rest }"),
+        ("|| 1", "fn() { 1 }"),
+        ("| | || x", "fn() { fn() { x } }"),
+        ("a || |b| b || c", "a || fn(b) { b || c }"),
+    ];
+    for (src, expected) in tests {
+        assert_eq!(parse_source(src)?, parse_source(expected)?, "{}", src);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_loop_control_in_lambda() {
+    let err = parse_source("while (x) { |y| if (y) { break; } }").expect_err("break should be outside the loop");
+    assert!(matches!(err, parser::Error::OutsideLoop { token: Token::Break, .. }));
+}
+
+#[test]
+fn parse_type_annotations() -> Result<(), parser::Error> {
+    let tests = vec![(
+        vec![
+            Token::Let,
+            Token::Identifier("f".to_string()),
+            Token::Colon,
+            Token::Function,
+            Token::LeftParen,
+            Token::LeftBracket,
+            Token::Identifier("int".to_string()),
+            Token::RightBracket,
+            Token::RightParen,
+            Token::Arrow,
+            Token::LeftBrace,
+            Token::Identifier("string".to_string()),
+            Token::Colon,
+            Token::Identifier("bool".to_string()),
+            Token::RightBrace,
+            Token::Assign,
+            Token::Function,
+            Token::LeftParen,
+            Token::Identifier("a".to_string()),
+            Token::Colon,
+            Token::Identifier("int".to_string()),
+            Token::Comma,
+            Token::Identifier("b".to_string()),
+            Token::RightParen,
+            Token::Arrow,
+            Token::Identifier("bool".to_string()),
+            Token::LeftBrace,
+            Token::RightBrace,
+            Token::Semicolon,
+        ], // let f: fn([int]) -> {string: bool} = fn(a: int, b) -> bool {};.
+        AST::new(vec![Statement::Let {
+            pattern: Pattern::Identifier("f".to_string()),
+            annotation: Some(Type::Function {
+                parameters: vec![Type::Array(boxx(Type::Named("int".to_string())))],
+                ret: boxx(Type::Hash(
+                    boxx(Type::Named("string".to_string())),
+                    boxx(Type::Named("bool".to_string())),
+                )),
+            }),
+            expression: Expression::Function {
+                name: None,
+                parameters: vec![
+                    Parameter {
+                        name: "a".to_string(),
+                        annotation: Some(Type::Named("int".to_string())),
+                        default: None,
+                    },
+                    Parameter {
+                        name: "b".to_string(),
+                        annotation: None,
+                        default: None,
+                    },
+                ],
+                rest: None,
+                ret: Some(Type::Named("bool".to_string())),
+                body: vec![],
+            },
+            doc: None,
+        }]),
+    )];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_hash_literals() -> Result<(), parser::Error> {
+    let tests = vec![
+        (
+            vec![Token::LeftBrace, Token::RightBrace], // {}.
+            AST::new(vec![Statement::Expression(Expression::Hash(vec![]))]),
+        ),
+        (
+            vec![
+                Token::LeftBrace,
+                Token::String("name".to_string()),
+                Token::Colon,
+                Token::String("x".to_string()),
+                Token::Comma,
+                Token::Integer(1),
+                Token::Colon,
+                Token::True,
+                Token::Comma,
+                Token::Identifier("key".to_string()),
+                Token::Colon,
+                Token::Function,
+                Token::LeftParen,
+                Token::RightParen,
+                Token::LeftBrace,
+                Token::RightBrace,
+                Token::RightBrace,
+            ], // {"name": "x", 1: true, key: fn(){}}.
+            AST::new(vec![Statement::Expression(Expression::Hash(vec![
+                (
+                    Expression::String("name".to_string()),
+                    Expression::String("x".to_string()),
+                ),
+                (Expression::Integer(1), Expression::Boolean(true)),
+                (
+                    Expression::Identifier("key".to_string()),
+                    Expression::Function {
+                        name: None,
+                        parameters: vec![],
+                        rest: None,
+                        ret: None,
+                        body: vec![],
+                    },
+                ),
+            ]))]),
+        ),
+        (
+            vec![
+                Token::LeftBrace,
+                Token::String("a".to_string()),
+                Token::Colon,
+                Token::LeftBrace,
+                Token::RightBrace,
+                Token::Comma,
+                Token::RightBrace,
+            ], // {"a": {},}.
+            AST::new(vec![Statement::Expression(Expression::Hash(vec![(
+                Expression::String("a".to_string()),
+                Expression::Hash(vec![]),
+            )]))]),
+        ),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_bad_hash_literal() {
+    let ast = Parser::new(
+        [
+            Token::LeftBrace,
+            Token::String("a".to_string()),
+            Token::Integer(1),
+            Token::RightBrace,
+        ]
+        .iter(),
+    )
+    .parse(); // {"a" 1}.
+    assert!(ast.is_err_and(|e| {
+        matches!(
+            e,
+            parser::Error::ExpectedToken(mismatch)
+                if mismatch.expected == [Token::Colon] && mismatch.found == Some(Token::Integer(1))
+        )
+    }));
+}
+
+#[test]
+fn parse_loops() -> Result<(), parser::Error> {
+    let tests = vec![
+        (
+            vec![
+                Token::While,
+                Token::LeftParen,
+                Token::Identifier("x".to_string()),
+                Token::RightParen,
+                Token::LeftBrace,
+                Token::If,
+                Token::LeftParen,
+                Token::True,
+                Token::RightParen,
+                Token::LeftBrace,
+                Token::Break,
+                Token::Semicolon,
+                Token::RightBrace,
+                Token::Continue,
+                Token::Semicolon,
+                Token::RightBrace,
+            ], // while (x) { if (true) { break; } continue; }.
+            AST::new(vec![Statement::While {
+                cond: Expression::Identifier("x".to_string()),
+                body: vec![
+                    Statement::Expression(Expression::If {
+                        cond: boxx(Expression::Boolean(true)),
+                        conseq: vec![Statement::Break],
+                        altern: None,
+                    }),
+                    Statement::Continue,
+                ],
+            }]),
+        ),
+        (
+            vec![
+                Token::For,
+                Token::LeftParen,
+                Token::Identifier("x".to_string()),
+                Token::In,
+                Token::Identifier("xs".to_string()),
+                Token::RightParen,
+                Token::LeftBrace,
+                Token::Identifier("x".to_string()),
+                Token::Semicolon,
+                Token::RightBrace,
+                Token::Integer(1),
+            ], // for (x in xs) { x; } 1.
+            AST::new(vec![
+                Statement::For {
+                    variable: "x".to_string(),
+                    iterable: Expression::Identifier("xs".to_string()),
+                    body: vec![Statement::Expression(Expression::Identifier("x".to_string()))],
+                },
+                Statement::Expression(Expression::Integer(1)),
+            ]),
+        ),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_loop_control_outside_loops() {
+    let tests = vec![
+        vec![Token::Break, Token::Semicolon], // break;.
+        vec![
+            Token::While,
+            Token::LeftParen,
+            Token::True,
+            Token::RightParen,
+            Token::LeftBrace,
+            Token::Function,
+            Token::LeftParen,
+            Token::RightParen,
+            Token::LeftBrace,
+            Token::Continue,
+            Token::Semicolon,
+            Token::RightBrace,
+            Token::RightBrace,
+        ], // while (true) { fn() { continue; } }.
+    ];
+    for test in tests {
+        let ast = Parser::new(test.iter()).parse();
+        assert!(ast.is_err_and(|e| matches!(e, parser::Error::OutsideLoop { .. })));
+    }
+}
+
+#[test]
+fn parse_call_expressions() -> Result<(), parser::Error> {
+    let tests = vec![(
+        vec![
+            Token::Identifier("add".to_string()),
+            Token::LeftParen,
+            Token::Integer(1),
+            Token::Comma,
+            Token::Integer(2),
+            Token::Asterisk,
+            Token::Integer(3),
+            Token::RightParen,
+            Token::LeftBracket,
+            Token::Integer(0),
+            Token::RightBracket,
+        ], // add(1, 2 * 3)[0].
+        AST::new(vec![Statement::Expression(Expression::Index {
+            left: boxx(Expression::Call {
+                function: boxx(Expression::Identifier("add".to_string())),
+                arguments: vec![
+                    Expression::Integer(1),
+                    Expression::Binary {
+                        left: boxx(Expression::Integer(2)),
+                        operator: Token::Asterisk,
+                        right: boxx(Expression::Integer(3)),
+                    },
+                ],
+                named: vec![],
+            }),
+            index: boxx(Expression::Integer(0)),
+        })]),
+    )];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_pipe_expressions() -> Result<(), parser::Error> {
+    let tests = vec![
+        (
+            vec![
+                Token::Identifier("data".to_string()),
+                Token::Pipe,
+                Token::Identifier("filter".to_string()),
+                Token::LeftParen,
+                Token::Identifier("isEven".to_string()),
+                Token::RightParen,
+                Token::Pipe,
+                Token::Identifier("len".to_string()),
+            ], // data |> filter(isEven) |> len.
+            AST::new(vec![Statement::Expression(Expression::Call {
+                function: boxx(Expression::Identifier("len".to_string())),
+                arguments: vec![Expression::Call {
+                    function: boxx(Expression::Identifier("filter".to_string())),
+                    arguments: vec![
+                        Expression::Identifier("data".to_string()),
+                        Expression::Identifier("isEven".to_string()),
+                    ],
+                    named: vec![],
+                }],
+                named: vec![],
+            })]),
+        ),
+        (
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::Assign,
+                Token::Integer(1),
+                Token::Plus,
+                Token::Integer(2),
+                Token::Pipe,
+                Token::Identifier("f".to_string()),
+                Token::LeftParen,
+                Token::RightParen,
+            ], // x = 1 + 2 |> f().
+            AST::new(vec![Statement::Expression(Expression::Assign {
+                target: boxx(Expression::Identifier("x".to_string())),
+                operator: None,
+                value: boxx(Expression::Call {
+                    function: boxx(Expression::Identifier("f".to_string())),
+                    arguments: vec![Expression::Binary {
+                        left: boxx(Expression::Integer(1)),
+                        operator: Token::Plus,
+                        right: boxx(Expression::Integer(2)),
+                    }],
+                    named: vec![],
+                }),
+            })]),
+        ),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_match_expressions() -> Result<(), parser::Error> {
+    let tests = vec![(
+        vec![
+            Token::Match,
+            Token::LeftParen,
+            Token::Identifier("x".to_string()),
+            Token::RightParen,
+            Token::LeftBrace,
+            Token::Minus,
+            Token::Integer(1),
+            Token::FatArrow,
+            Token::String("neg".to_string()),
+            Token::Comma,
+            Token::LeftBracket,
+            Token::Identifier("a".to_string()),
+            Token::Comma,
+            Token::Identifier("_".to_string()),
+            Token::RightBracket,
+            Token::If,
+            Token::Identifier("a".to_string()),
+            Token::FatArrow,
+            Token::Identifier("a".to_string()),
+            Token::Comma,
+            Token::LeftBrace,
+            Token::String("k".to_string()),
+            Token::Colon,
+            Token::True,
+            Token::RightBrace,
+            Token::FatArrow,
+            Token::LeftBrace,
+            Token::RightBrace,
+            Token::Comma,
+            Token::Identifier("_".to_string()),
+            Token::FatArrow,
+            Token::Integer(0),
+            Token::Comma,
+            Token::RightBrace,
+        ], // match (x) { -1 => "neg", [a, _] if a => a, {"k": true} => {}, _ => 0, }.
+        AST::new(vec![Statement::Expression(Expression::Match {
+            subject: boxx(Expression::Identifier("x".to_string())),
+            arms: vec![
+                MatchArm {
+                    pattern: Pattern::Integer(-1),
+                    guard: None,
+                    body: Expression::String("neg".to_string()),
+                },
+                MatchArm {
+                    pattern: Pattern::Array {
+                        elements: vec![Pattern::Identifier("a".to_string()), Pattern::Wildcard],
+                        rest: None,
+                    },
+                    guard: Some(Expression::Identifier("a".to_string())),
+                    body: Expression::Identifier("a".to_string()),
+                },
+                MatchArm {
+                    pattern: Pattern::Hash(vec![(Expression::String("k".to_string()), Pattern::Boolean(true))]),
+                    guard: None,
+                    body: Expression::Hash(vec![]),
+                },
+                MatchArm {
+                    pattern: Pattern::Wildcard,
+                    guard: None,
+                    body: Expression::Integer(0),
+                },
+            ],
+        })]),
+    )];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_unreachable_match_arm() {
+    let ast = Parser::new(
+        [
+            Token::Match,
+            Token::LeftParen,
+            Token::Identifier("x".to_string()),
+            Token::RightParen,
+            Token::LeftBrace,
+            Token::Identifier("_".to_string()),
+            Token::FatArrow,
+            Token::Integer(0),
+            Token::Comma,
+            Token::Integer(1),
+            Token::FatArrow,
+            Token::Integer(1),
+            Token::RightBrace,
+        ]
+        .iter(),
+    )
+    .parse(); // match (x) { _ => 0, 1 => 1 }.
+    assert!(ast.is_err_and(|e| matches!(e, parser::Error::UnreachableArm { pattern, .. } if *pattern == Pattern::Integer(1))));
+}
+
+#[test]
+fn parse_logical_expressions() -> Result<(), parser::Error> {
+    let tests = vec![
+        (
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Or,
+                Token::Identifier("b".to_string()),
+                Token::And,
+                Token::Identifier("c".to_string()),
+            ], // a || b && c.
+            AST::new(vec![Statement::Expression(Expression::Logical {
+                left: boxx(Expression::Identifier("a".to_string())),
+                operator: Token::Or,
+                right: boxx(Expression::Logical {
+                    left: boxx(Expression::Identifier("b".to_string())),
+                    operator: Token::And,
+                    right: boxx(Expression::Identifier("c".to_string())),
+                }),
+            })]),
+        ),
+        (
+            vec![
+                Token::Integer(1),
+                Token::Equal,
+                Token::Integer(1),
+                Token::And,
+                Token::Bang,
+                Token::False,
+                Token::And,
+                Token::True,
+            ], // 1 == 1 && !false && true.
+            AST::new(vec![Statement::Expression(Expression::Logical {
+                left: boxx(Expression::Logical {
+                    left: boxx(Expression::Binary {
+                        left: boxx(Expression::Integer(1)),
+                        operator: Token::Equal,
+                        right: boxx(Expression::Integer(1)),
+                    }),
+                    operator: Token::And,
+                    right: boxx(Expression::Unary {
+                        operator: Token::Bang,
+                        expression: boxx(Expression::Boolean(false)),
+                    }),
+                }),
+                operator: Token::And,
+                right: boxx(Expression::Boolean(true)),
+            })]),
+        ),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_assignments() -> Result<(), parser::Error> {
+    let tests = vec![
+        (
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::Assign,
+                Token::Identifier("x".to_string()),
+                Token::Plus,
+                Token::Integer(1),
+                Token::Semicolon,
+            ], // x = x + 1;.
+            AST::new(vec![Statement::Expression(Expression::Assign {
+                target: boxx(Expression::Identifier("x".to_string())),
+                operator: None,
+                value: boxx(Expression::Binary {
+                    left: boxx(Expression::Identifier("x".to_string())),
+                    operator: Token::Plus,
+                    right: boxx(Expression::Integer(1)),
+                }),
+            })]),
+        ),
+        (
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Assign,
+                Token::Identifier("b".to_string()),
+                Token::LeftBracket,
+                Token::Integer(0),
+                Token::RightBracket,
+                Token::Assign,
+                Token::Integer(1),
+            ], // a = b[0] = 1.
+            AST::new(vec![Statement::Expression(Expression::Assign {
+                target: boxx(Expression::Identifier("a".to_string())),
+                operator: None,
+                value: boxx(Expression::Assign {
+                    target: boxx(Expression::Index {
+                        left: boxx(Expression::Identifier("b".to_string())),
+                        index: boxx(Expression::Integer(0)),
+                    }),
+                    operator: None,
+                    value: boxx(Expression::Integer(1)),
+                }),
+            })]),
+        ),
+        (
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::AsteriskAssign,
+                Token::Integer(2),
+                Token::Plus,
+                Token::Integer(1),
+            ], // x *= 2 + 1.
+            AST::new(vec![Statement::Expression(Expression::Assign {
+                target: boxx(Expression::Identifier("x".to_string())),
+                operator: Some(Token::Asterisk),
+                value: boxx(Expression::Binary {
+                    left: boxx(Expression::Integer(2)),
+                    operator: Token::Plus,
+                    right: boxx(Expression::Integer(1)),
+                }),
+            })]),
+        ),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_bad_assignment_targets() {
+    let tests = vec![
+        vec![Token::Integer(1), Token::Assign, Token::Integer(2)], // 1 = 2.
+        vec![
+            Token::Identifier("a".to_string()),
+            Token::Plus,
+            Token::Identifier("b".to_string()),
+            Token::MinusAssign,
+            Token::Integer(2),
+        ], // a + b -= 2.
+    ];
+    for test in tests {
+        let ast = Parser::new(test.iter()).parse();
+        assert!(ast.is_err_and(|e| matches!(e, parser::Error::InvalidAssignmentTarget { .. })));
+    }
+}
+
+#[test]
+fn parse_too_deep_nesting() {
+    const DEPTH: usize = 100_000;
+    let nested = |open: Vec<Token>, inner: Vec<Token>, close: Vec<Token>| {
+        let mut tokens: Vec<Token> = open.iter().cycle().take(open.len() * DEPTH).cloned().collect();
+        tokens.extend(inner);
+        tokens.extend(close.iter().cycle().take(close.len() * DEPTH).cloned());
+        tokens
+    };
+    let x = Token::Identifier("x".to_string());
+    let tests = [
+        nested(vec![Token::LeftParen], vec![x.clone()], vec![Token::RightParen]),
+        nested(vec![Token::Bang], vec![x.clone()], vec![]),
+        nested(vec![Token::LeftBracket], vec![], vec![Token::RightBracket]),
+        nested(
+            vec![Token::While, Token::LeftParen, Token::True, Token::RightParen, Token::LeftBrace],
+            vec![],
+            vec![Token::RightBrace],
+        ),
+        [Token::Let]
+            .into_iter()
+            .chain(nested(vec![Token::LeftBracket], vec![x.clone()], vec![Token::RightBracket]))
+            .chain([Token::Assign, x.clone(), Token::Semicolon])
+            .collect(),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.iter()).parse();
+        assert!(ast.is_err_and(|e| matches!(e, parser::Error::TooDeep { limit: 128, .. })));
+    }
+}
+
+#[test]
+fn parse_too_long_chains() {
+    const TERMS: usize = 200_000;
+    let one = Token::Integer(1);
+    let x = Token::Identifier("x".to_string());
+    let chain = |first: Vec<Token>, link: Vec<Token>| -> Vec<Token> {
+        first.into_iter().chain(link.iter().cycle().take(link.len() * TERMS).cloned()).collect()
+    };
+    let tests = [
+        chain(vec![one.clone()], vec![Token::Plus, one.clone()]),
+        chain(vec![x.clone()], vec![Token::LeftBracket, one.clone(), Token::RightBracket]),
+        chain(vec![x.clone()], vec![Token::LeftParen, Token::RightParen]),
+        chain(vec![x.clone()], vec![Token::Pipe, x.clone()]),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.iter()).parse();
+        assert!(ast.is_err_and(|e| matches!(e, parser::Error::TooDeep { limit: 128, .. })));
+    }
+    // Shorter chains are parsed, as a chain only counts one level every 64 links.
+    let terms = |link: Vec<Token>, n: usize| -> Vec<Token> {
+        let first = link[1..].to_vec();
+        first.into_iter().chain(link.iter().cycle().take(link.len() * (n - 1)).cloned()).collect()
+    };
+    for tokens in [terms(vec![Token::Plus, one], 1000), terms(vec![Token::Pipe, x], 1000)] {
+        assert!(Parser::new(tokens.iter()).parse().is_ok());
+    }
+}
+
+#[test]
+fn parse_with_depth_limit() -> Result<(), parser::Error> {
+    let (tokens, spans) = lex("-(-(x))");
+    Parser::new(tokens.iter()).with_depth_limit(5).parse()?;
+    let ast = Parser::new(tokens.iter()).with_spans(spans).with_depth_limit(4).parse();
+    // The limit is exceeded at `x`.
+    assert!(ast.is_err_and(|e| matches!(
+        e,
+        parser::Error::TooDeep { limit: 4, span: Some(span) } if span.start.column == 5
+    )));
+    Ok(())
+}
+
+#[test]
+fn parse_into_arena() -> Result<(), parser::Error> {
+    // let x = -a + b; x += 1;
+    let tokens = [
+        Token::Let,
+        Token::Identifier("x".to_string()),
+        Token::Assign,
+        Token::Minus,
+        Token::Identifier("a".to_string()),
+        Token::Plus,
+        Token::Identifier("b".to_string()),
+        Token::Semicolon,
+        Token::Identifier("x".to_string()),
+        Token::PlusAssign,
+        Token::Integer(1),
+        Token::Semicolon,
+    ];
+    let ast = Parser::new(tokens.iter()).parse_arena()?;
+    let arena = ast.arena();
+    assert_eq!(arena.len(), 9);
+
+    let StmtNode::Let { expression, .. } = &arena[ast.statements()[0]] else {
+        panic!("expected a let statement, got {:?}", arena[ast.statements()[0]]);
+    };
+    let ExprNode::Binary { left, operator, right } = &arena[*expression] else {
+        panic!("expected a binary expression, got {:?}", arena[*expression]);
+    };
+    assert_eq!(operator, &Token::Plus);
+    assert!(matches!(arena[*left], ExprNode::Unary { .. }));
+    assert_eq!(arena[*right], ExprNode::Identifier("b".to_string()));
+
+    // The operator of a compound assignment stays on the assignment.
+    let StmtNode::Expression(assign) = &arena[ast.statements()[1]] else {
+        panic!("expected an expression statement, got {:?}", arena[ast.statements()[1]]);
+    };
+    let ExprNode::Assign { operator, value, .. } = &arena[*assign] else {
+        panic!("expected an assignment, got {:?}", arena[*assign]);
+    };
+    assert_eq!(operator, &Some(Token::Plus));
+    assert_eq!(arena[*value], ExprNode::Integer(1));
+
+    let boxed = Parser::new(tokens.iter()).parse()?;
+    assert_eq!(ast.to_ast(), boxed);
+    assert_eq!(AST::from(ast), boxed);
+    Ok(())
+}
+
+#[test]
+fn parse_single_expression() -> Result<(), parser::Error> {
+    // a + 1 > b.
+    let tokens = [
+        Token::Identifier("a".to_string()),
+        Token::Plus,
+        Token::Integer(1),
+        Token::GreaterThan,
+        Token::Identifier("b".to_string()),
+    ];
+    let exp = Parser::new(tokens.iter()).parse_expression()?;
+    let expected = Expression::Binary {
+        left: boxx(Expression::Binary {
+            left: boxx(Expression::Identifier("a".to_string())),
+            operator: Token::Plus,
+            right: boxx(Expression::Integer(1)),
+        }),
+        operator: Token::GreaterThan,
+        right: boxx(Expression::Identifier("b".to_string())),
+    };
+    assert_eq!(exp, expected);
+    Ok(())
+}
+
+#[test]
+fn parse_expression_with_leftover_tokens() {
+    let tests = [
+        ("1;", Token::Semicolon, 2),
+        ("f())", Token::RightParen, 4),
+        ("1 2 + 3", Token::Integer(2), 3),
+    ];
+    for (src, token, column) in tests {
+        let (tokens, spans) = lex(src);
+        let err = Parser::new(tokens.iter()).with_spans(spans).parse_expression().unwrap_err();
+        assert!(
+            matches!(&err, parser::Error::TrailingToken { token: t, span: Some(span) } if *t == token && span.start.column == column),
+            "{}: {}",
+            src,
+            err
+        );
+    }
+}
+
+#[test]
+fn look_up_nodes_by_id() -> Result<(), parser::Error> {
+    // let x = f(1); while (x) { break; }
+    let tokens = [
+        Token::Let,
+        Token::Identifier("x".to_string()),
+        Token::Assign,
+        Token::Identifier("f".to_string()),
+        Token::LeftParen,
+        Token::Integer(1),
+        Token::RightParen,
+        Token::Semicolon,
+        Token::While,
+        Token::LeftParen,
+        Token::Identifier("x".to_string()),
+        Token::RightParen,
+        Token::LeftBrace,
+        Token::Break,
+        Token::Semicolon,
+        Token::RightBrace,
+    ];
+    let ast = Parser::new(tokens.iter()).parse_arena()?;
+    let nodes: Vec<(NodeId, &Node)> = ast.nodes().collect();
+    assert_eq!(nodes.len(), 7);
+    for (i, &(id, node)) in nodes.iter().enumerate() {
+        assert_eq!(id, NodeId::new(i));
+        assert_eq!(ast.node(id), Some(node));
+    }
+    assert_eq!(ast.node(NodeId::new(7)), None);
+
+    // Nodes are stored after their children.
+    let arena = ast.arena();
+    let [let_x, while_x] = *ast.statements() else {
+        panic!("expected two statements, got {:?}", ast.statements());
+    };
+    assert_eq!((let_x.id(), while_x.id()), (NodeId::new(3), NodeId::new(6)));
+    let StmtNode::Let { expression, .. } = &arena[let_x] else {
+        panic!("expected a let statement, got {:?}", arena[let_x]);
+    };
+    assert_eq!(expression.id(), NodeId::new(2));
+    let ExprNode::Call { function, arguments, .. } = &arena[*expression] else {
+        panic!("expected a call, got {:?}", arena[*expression]);
+    };
+    assert_eq!((function.id(), arguments[0].id()), (NodeId::new(0), NodeId::new(1)));
+    assert_eq!(ast.node(NodeId::new(1)), Some(&Node::Expression(ExprNode::Integer(1))));
+    let StmtNode::While { cond, body } = &arena[while_x] else {
+        panic!("expected a while loop, got {:?}", arena[while_x]);
+    };
+    assert_eq!((cond.id(), body[0].id()), (NodeId::new(4), NodeId::new(5)));
+    assert_eq!(ast.node(NodeId::new(5)), Some(&Node::Statement(StmtNode::Break)));
+
+    // Parsing the same source again assigns the same IDs.
+    let again = Parser::new(tokens.iter()).parse_arena()?;
+    assert!(again.nodes().eq(ast.nodes()));
+    Ok(())
+}
+
+#[test]
+fn parse_node_ids_of_boxed_ast() -> Result<(), parser::Error> {
+    let sources = [
+        "let x = f(1); while (x) { break; }",
+        "let f = fn(a, b: int = 2 * c) { if (a) { b } else { -a } }; f(1, b: [2, 3][0]);",
+        "x |> f(y) |> g; [1, 2] |> map(|x| x * 2); export let h = {\"a\": z = y += 1};",
+        "match (p) { [a, ...r] if a > 0 => a || r, _ => macro(q) { q } }; for (i in xs) { return; }",
+    ];
+    for src in sources {
+        let (tokens, spans) = lex(src);
+        let arena = Parser::new(tokens.iter()).with_spans(spans.clone()).parse_arena()?;
+        let ast = Parser::new(tokens.iter()).with_spans(spans).parse()?;
+        assert_eq!(ast.nodes().count(), arena.nodes().count(), "{}", src);
+        for (id, node) in arena.nodes() {
+            let boxed = ast.node(id).expect("node should have the ID");
+            assert_eq!(leaf(boxed), arena_leaf(node), "{} at {:?}", src, id);
+        }
+        for (st, id) in ast.statements().iter().zip(arena.statements()) {
+            assert_eq!(ast.node(id.id()), Some(NodeRef::Statement(st)), "{}", src);
+        }
+        assert_eq!(ast.node(NodeId::new(arena.nodes().count())), None);
+    }
+    Ok(())
+}
+
+/// Returns the value of a literal or identifier node.
+fn leaf(node: NodeRef) -> Option<String> {
+    match node {
+        NodeRef::Expression(Expression::Identifier(s) | Expression::String(s)) => Some(s.clone()),
+        NodeRef::Expression(Expression::Integer(i)) => Some(i.to_string()),
+        _ => None,
+    }
+}
+
+fn arena_leaf(node: &Node) -> Option<String> {
+    match node {
+        Node::Expression(ExprNode::Identifier(s) | ExprNode::String(s)) => Some(s.clone()),
+        Node::Expression(ExprNode::Integer(i)) => Some(i.to_string()),
+        _ => None,
+    }
+}
+
+#[test]
+fn parse_doc_comments() -> Result<(), parser::Error> {
+    let tests = vec![
+        (
+            vec![
+                Token::DocComment("Adds one.".to_string()),
+                Token::DocComment("Really.".to_string()),
+                Token::Let,
+                Token::Identifier("inc".to_string()),
+                Token::Assign,
+                Token::Integer(1),
+                Token::Semicolon,
+            ],
+            AST::new(vec![Statement::Let {
+                pattern: Pattern::Identifier("inc".to_string()),
+                annotation: None,
+                expression: Expression::Integer(1),
+                doc: Some("Adds one.\nReally.".to_string()),
+            }]),
+        ),
+        (
+            vec![
+                Token::DocComment("Exported.".to_string()),
+                Token::Export,
+                Token::Let,
+                Token::Identifier("x".to_string()),
+                Token::Assign,
+                Token::Integer(1),
+                Token::Semicolon,
+            ],
+            AST::new(vec![Statement::Export(boxx(Statement::Let {
+                pattern: Pattern::Identifier("x".to_string()),
+                annotation: None,
+                expression: Expression::Integer(1),
+                doc: Some("Exported.".to_string()),
+            }))]),
+        ),
+    ];
+    for test in tests {
+        let ast = Parser::new(test.0.iter()).parse()?;
+        assert_eq!(ast, test.1);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_dangling_doc_comments() {
+    // Only bindings are documented.
+    let tests = [
+        ("/// One.\n1;", (1, 1)),
+        ("let x = 1;\n/// At the end.", (2, 1)),
+        ("if (x) {\n  /// Returns.\n  return 1;\n}", (2, 3)),
+        ("fn f() {\n  1\n  /// Before the brace.\n}", (3, 3)),
+    ];
+    for (src, (line, column)) in tests {
+        let (tokens, spans) = lex(src);
+        let ast = Parser::new(tokens.iter()).with_spans(spans).parse();
+        assert!(
+            ast.as_ref().is_err_and(|e| matches!(
+                e,
+                parser::Error::DanglingDocComment { span: Some(span) }
+                    if (span.start.line, span.start.column) == (line, column)
+            )),
+            "{}: {:?}",
+            src,
+            ast
+        );
+    }
+}
+
+#[test]
+fn parse_statements_separated_by_lines() -> Result<(), parser::Error> {
+    let tests = [
+        ("let x = 1\nreturn x\nx", "let x = 1; return x; x;"),
+        ("import \"a.mk\" as a\nbreaker()", "import \"a.mk\" as a; breaker();"),
+        ("xs\n|> f\n+ 1", "xs |> f + 1;"),
+        ("x\n|| y\n&& z", "x || y && z;"),
+        ("x\n-1\n(y)\n[z]", "x; -1; (y); [z];"),
+        ("if (x) { 1 } y", "if (x) { 1 }; y;"),
+        ("f(\n1,\n2\n)", "f(1, 2);"),
+    ];
+    for (src, expected) in tests {
+        assert_eq!(parse_source(src)?, parse_source(expected)?, "{}", src);
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_statements_sharing_a_line() {
+    let tests = [
+        ("1 2", Token::Integer(2), (1, 3)),
+        ("let x = 1 let y = 2", Token::Let, (1, 11)),
+        ("f(x) g\nh", Token::Identifier("g".to_string()), (1, 6)),
+        ("return x\n{} y", Token::Identifier("y".to_string()), (2, 4)),
+        ("let s = \"a\nb\" x", Token::Identifier("x".to_string()), (2, 4)),
+        ("fn() { 1 } x", Token::Identifier("x".to_string()), (1, 12)),
+        ("{\"k\": 1} x", Token::Identifier("x".to_string()), (1, 10)),
+    ];
+    for (src, token, position) in tests {
+        let err = parse_source(src).expect_err(src);
+        assert!(
+            matches!(&err, parser::Error::MissingSeparator { token: t, span: Some(span) }
+                if *t == token && (span.start.line, span.start.column) == position),
+            "{}: {}",
+            src,
+            err
+        );
+    }
+}
+
+#[test]
+fn parse_error_messages() {
+    let tests = [
+        ("f(1; 2)", "expected one of `)`, `,` but found `;` in call arguments"),
+        ("fn(x;) {}", "expected one of `:`, `=`, `)`, `,` but found `;` in function parameters"),
+        ("let x 5", "expected one of `:`, `=` but found `5` in let statement"),
+        ("import x as y", "expected a string but found `x` in import statement"),
+        ("for (1 in xs) {}", "expected an identifier but found `1` in for loop"),
+        ("let [...xs, y] = z", "expected `]` but found `y` in array pattern"),
+        ("if (x) { y", "expected `}` but reached the end of the source in block"),
+        ("let x = (1", "expected `)` but reached the end of the source in parentheses"),
+        (
+            "let x = ;",
+            "expected one of an identifier, an integer, a string, `true`, `false`, `!`, `-`, `(`, `[`, `{`, \
+             `if`, `match`, `fn`, `|`, `||`, `macro` but found `;` in let statement",
+        ),
+        (
+            "f(1, ;)",
+            "expected one of `)`, an identifier, an integer, a string, `true`, `false`, `!`, `-`, `(`, `[`, `{`, \
+             `if`, `match`, `fn`, `|`, `||`, `macro` but found `;` in call arguments",
+        ),
+        (
+            "let x =",
+            "expected one of an identifier, an integer, a string, `true`, `false`, `!`, `-`, `(`, `[`, `{`, \
+             `if`, `match`, `fn`, `|`, `||`, `macro` but reached the end of the source in let statement",
+        ),
+        (
+            "let (x) = 1",
+            "expected one of an identifier, an integer, a string, `true`, `false`, `-`, `[`, `{` \
+             but found `(` in let statement",
+        ),
+        (
+            "let {[x]} = y",
+            "expected one of `}`, an identifier, an integer, a string, `true`, `false` but found `[` in hash pattern",
+        ),
+        ("let x: 1 = 2", "expected one of an identifier, `[`, `{`, `fn` but found `1` in let statement"),
+    ];
+    for (src, message) in tests {
+        let err = parse_source(src).expect_err(src);
+        assert_eq!(err.to_string(), message, "{}", src);
+    }
+}
+
+#[test]
+fn parse_error_spans() {
+    let src = "let f = fn(a,\n  b;) {}";
+    let Err(parser::Error::ExpectedToken(mismatch)) = parse_source(src) else {
+        panic!("{} should fail with an unexpected token", src);
+    };
+    assert_eq!(mismatch.found, Some(Token::Semicolon));
+    let span = mismatch.span.expect("the offending token should have a span");
+    assert_eq!((span.start.line, span.start.column, span.start.offset), (2, 4, 17));
+    let context = mismatch.context.expect("the error should have a context");
+    assert_eq!(context.construct, Construct::FunctionParameters);
+    let opening = context.opening.expect("the opening token should have a span");
+    assert_eq!((opening.start.line, opening.start.column, opening.end.column), (1, 11, 12));
+
+    // Without spans, the error still tells what was being parsed.
+    let tokens = [Token::LeftBracket, Token::Integer(1), Token::Integer(2)]; // [1 2.
+    let Err(parser::Error::ExpectedToken(mismatch)) = Parser::new(tokens.iter()).parse() else {
+        panic!("[1 2 should fail with an unexpected token");
+    };
+    assert_eq!(mismatch.span, None);
+    assert_eq!(mismatch.context.map(|context| (context.construct, context.opening)), Some((Construct::Array, None)));
+}
+
+#[test]
+fn parse_error_locations() {
+    let tests = [
+        ("let [a, 1] = xs;", (1, 5), (1, 11)),
+        ("if (x) { export let y = 1; }", (1, 10), (1, 16)),
+        ("export let [x] = xs;", (1, 8), (1, 21)),
+        ("break;", (1, 1), (1, 6)),
+        ("a + b = 2", (1, 1), (1, 6)),
+        ("match (x) { _ => 0, 1 => 1 }", (1, 21), (1, 22)),
+        ("fn(...r, a) {}", (1, 10), (1, 11)),
+        ("fn(a = 1, b) {}", (1, 11), (1, 12)),
+        ("fn(a, a) {}", (1, 7), (1, 8)),
+        ("f(a: 1, 2)", (1, 9), (1, 10)),
+        ("f(a: 1,\n  a: 2)", (2, 3), (2, 7)),
+    ];
+    for (src, start, end) in tests {
+        let err = parse_source(src).expect_err(src);
+        let span = error_span(&err).unwrap_or_else(|| panic!("{}: {} should have a span", src, err));
+        assert_eq!(
+            ((span.start.line, span.start.column), (span.end.line, span.end.column)),
+            (start, end),
+            "{}: {}",
+            src,
+            err
+        );
+    }
+    // Without spans, the errors are not located.
+    let tokens = [Token::Break];
+    let err = Parser::new(tokens.iter()).parse().expect_err("break should be outside a loop");
+    assert!(matches!(err, parser::Error::OutsideLoop { span: None, .. }));
+}
+
+/// Returns the location of an error about a construct rather than a missing token.
+fn error_span(err: &parser::Error) -> Option<Span> {
+    match err {
+        parser::Error::InvalidAssignmentTarget { span, .. }
+        | parser::Error::UnreachableArm { span, .. }
+        | parser::Error::ExportPattern { span, .. }
+        | parser::Error::LiteralPattern { span, .. }
+        | parser::Error::ParameterAfterRest { span, .. }
+        | parser::Error::RequiredAfterDefault { span, .. }
+        | parser::Error::DuplicateParameter { span, .. }
+        | parser::Error::PositionalAfterNamed { span, .. }
+        | parser::Error::DuplicateArgument { span, .. }
+        | parser::Error::OutsideLoop { span, .. }
+        | parser::Error::NotTopLevel { span, .. } => *span,
+        _ => None,
+    }
+}
+
+/// Parses the source, telling the parser where each token is.
+fn parse_source(src: &str) -> Result<AST, parser::Error> {
+    let (tokens, spans) = lex(src);
+    Parser::new(tokens.iter()).with_spans(spans).parse()
+}
+
+/// Returns the tokens of the source and their spans.
+fn lex(src: &str) -> (Vec<Token>, Vec<Span>) {
+    let mut tokens = Vec::new();
+    let mut spans = Vec::new();
+    for lexeme in Lexer::new(std::io::Cursor::new(src)).lexemes() {
+        let lexeme = lexeme.expect("source should be lexed");
+        if let LexemeKind::Token(tok) = lexeme.kind {
+            tokens.push(tok);
+            spans.push(lexeme.span);
+        }
+    }
+    (tokens, spans)
+}
+
 /// Just an abbreviated Box::new(T).
 #[inline(always)]
 fn boxx<T>(val: T) -> Box<T> {
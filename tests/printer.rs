@@ -0,0 +1,109 @@
+use std::io;
+
+use monkeylang::lexer::{Lexer, Token};
+use monkeylang::parser::*;
+
+#[test]
+fn print_minimal_parentheses() {
+    let tests = [
+        ("((1) + (2));", "1 + 2;"),
+        ("(1 + 2) * 3;", "(1 + 2) * 3;"),
+        ("1 + (2 * 3);", "1 + 2 * 3;"),
+        ("a - (b - c);", "a - (b - c);"),
+        ("(a - b) - c;", "a - b - c;"),
+        ("-(a + b);", "-(a + b);"),
+        ("(-a) + b;", "-a + b;"),
+        ("-(-a);", "--a;"),
+        ("!(f(x)[0]);", "!f(x)[0];"),
+        ("(f |> g)(x);", "g(f)(x);"),
+        ("(a || b) && c;", "(a || b) && c;"),
+        ("a || (b && c);", "a || b && c;"),
+        ("(x = 1) + 2;", "(x = 1) + 2;"),
+        ("a = (b = c);", "a = b = c;"),
+        ("x += 1;", "x += 1;"),
+        ("a[i()] -= 1;", "a[i()] -= 1;"),
+        ("(fn(x) { x })(1);", "fn(x) {\n    x;\n}(1);"),
+    ];
+    for (src, printed) in tests {
+        assert_eq!(parse(src).to_string(), printed);
+    }
+}
+
+#[test]
+fn print_indented_blocks() {
+    let src = r#"
+import "lib.mk" as lib;
+/// Adds two integers.
+/// Both must be integers.
+export let add = fn(a: int, b: int) -> int { a + b };
+let f = fn() {
+/// Nested.
+let y = 1;
+if (x > 1) { while (true) { break; } } else { for (i in [1, 2]) { continue; } }
+};
+fn g(n) { n }
+let {name, "a": [first, ...rest]} = {"name": "x", "a": []};
+match (n) { 0 => "zero", -1 => "minus one", m if m > 0 => "positive", _ => {} };
+"#;
+    let printed = r#"import "lib.mk" as lib;
+/// Adds two integers.
+/// Both must be integers.
+export let add = fn(a: int, b: int) -> int {
+    a + b;
+};
+let f = fn() {
+    /// Nested.
+    let y = 1;
+    if (x > 1) {
+        while (true) {
+            break;
+        }
+    } else {
+        for (i in [1, 2]) {
+            continue;
+        }
+    };
+};
+fn g(n) {
+    n;
+}
+let {name, "a": [first, ...rest]} = {"name": "x", "a": []};
+match (n) {
+    0 => "zero",
+    -1 => "minus one",
+    m if m > 0 => "positive",
+    _ => {},
+};"#;
+    assert_eq!(parse(src).to_string(), printed);
+}
+
+#[test]
+fn print_and_parse_back() {
+    let sources = [
+        "let x: [int] = [1, 2 * (3 + 4), -5];",
+        "let f: fn(int, {string: bool}) -> int = fn(a, b) { return a; };",
+        "let h = fn() { return; }; let k = fn() { if (x) { return } };",
+        "let unless = macro(cond, cons) { quote(if (!(unquote(cond))) { unquote(cons) }); };",
+        "x[0] = y -= 2; z = {\"k\": if (a) { b } else { c }}[\"k\"];",
+        "let r = 1 - (2 - 3) / (4 * 5) < 6 == (7 > 8) != !true;",
+        "let g = fn() { fn() { 1 } }()();",
+        "fn add(a, b) -> int { a + b } export fn id(x) { x } let f = fn(x) { add(x, 1) };",
+        "fn f(x, y: int = 10 * 2, ...rest) { f(x, y: y - 1) } let g = fn(...xs) { f(z: fn(a = 1) { a }) };",
+        "[1, 2] |> map(fn(x) { x * 2 }) |> sum;",
+        "[1, 2] |> filter(|x| x > 1 || x < 0) |> reduce(|| 0, |a, b| { a + b });",
+        "while (i < 10) { if (i == 5) { break; }; i += 1; };",
+        "match ([a, b]) { [_, ...r] if r == [] => r, {1: x, \"y\": false} => x, _ => -(a + b) };",
+    ];
+    for src in sources {
+        let ast = parse(src);
+        let printed = ast.to_string();
+        assert_eq!(parse(&printed), ast, "printed as {}", printed);
+    }
+}
+
+fn parse(src: &str) -> AST {
+    let tokens: Vec<Token> = Lexer::new(io::Cursor::new(src))
+        .collect::<Result<_, _>>()
+        .expect("source should be lexed");
+    Parser::new(tokens.iter()).parse().expect("source should be parsed")
+}
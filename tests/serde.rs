@@ -0,0 +1,44 @@
+#![cfg(feature = "serde")]
+
+use std::io;
+
+use monkeylang::lexer::{Lexer, Token};
+use monkeylang::parser::*;
+
+#[test]
+fn reload_ast_from_json() {
+    let src = r#"
+import "lib.mk" as lib;
+export let add = fn(a: int, b: int) -> int { a + b };
+let {name, "xs": [first, ...rest]} = lib["config"];
+for (x in rest) { if (x > first && x != 0) { break; } else { continue; } }
+let m = macro(a) { quote(unquote(a) * 2) };
+match (name) { "monkey" => true, n if n == "" => false, _ => -1 };
+"#;
+    let ast = parse(src);
+    let json = serde_json::to_string(&ast).expect("AST should be serialized");
+    let reloaded: AST = serde_json::from_str(&json).expect("AST should be deserialized");
+    assert_eq!(reloaded, ast);
+}
+
+#[test]
+fn reload_tokens_from_json() {
+    let tokens = [
+        Token::Let,
+        Token::Identifier("x".to_string()),
+        Token::PlusAssign,
+        Token::Integer(-5),
+        Token::String("five".to_string()),
+        Token::Ellipsis,
+    ];
+    let json = serde_json::to_string(&tokens).expect("tokens should be serialized");
+    let reloaded: Vec<Token> = serde_json::from_str(&json).expect("tokens should be deserialized");
+    assert_eq!(reloaded, tokens);
+}
+
+fn parse(src: &str) -> AST {
+    let tokens: Vec<Token> = Lexer::new(io::Cursor::new(src))
+        .collect::<Result<_, _>>()
+        .expect("source should be lexed");
+    Parser::new(tokens.iter()).parse().expect("source should be parsed")
+}
@@ -0,0 +1,55 @@
+use std::io;
+
+use monkeylang::lexer::{Lexer, Token};
+use monkeylang::parser::*;
+
+#[test]
+fn write_expressions() {
+    let tests = [
+        ("1 + 2 * -x;", "(program (expression (+ 1 (* 2 (- x)))))"),
+        ("a && !b;", "(program (expression (&& a (! b))))"),
+        ("f(1, [\"a\\\"])[0];", "(program (expression (index (call f 1 (array \"a\\\\\")) 0)))"),
+        ("{1: true};", "(program (expression (hash (1 true))))"),
+        ("x = y;", "(program (expression (= x y)))"),
+        (
+            "if (a) { b } else { c; }",
+            "(program (expression (if a (block (expression b)) (block (expression c)))))",
+        ),
+        (
+            "fn(x: int, y) -> [int] { return [x]; };",
+            "(program (expression (fn (params (x int) y) (type (array int)) (block (return (array x))))))",
+        ),
+        ("macro(a) { quote(a) };", "(program (expression (macro (params a) (block (expression (call quote a))))))"),
+    ];
+    for (src, sexp) in tests {
+        assert_eq!(to_sexp(&parse(src)), sexp);
+    }
+}
+
+#[test]
+fn write_statements() {
+    let src = r#"
+import "lib.mk" as lib;
+/// A "hash".
+export let n: {string: fn(int) -> bool} = {};
+let [a, ...rest] = [];
+for (x in rest) { while (true) { break; continue; } }
+match (a) { {"k": v} if v => 1, _ => 2 };
+"#;
+    let sexp = concat!(
+        "(program ",
+        "(import \"lib.mk\" lib) ",
+        "(export (let (doc \"A \\\"hash\\\".\") n (type (hash string (fn (int) bool))) (hash))) ",
+        "(let (array a (... rest)) (array)) ",
+        "(for x rest (block (while true (block (break) (continue))))) ",
+        "(expression (match a (arm (hash (\"k\" v)) (if v) 1) (arm _ 2))))",
+    );
+    assert_eq!(to_sexp(&parse(src)), sexp);
+}
+
+fn parse(src: &str) -> AST {
+    let tokens: Vec<Token> = Lexer::new(io::Cursor::new(src))
+        .collect::<Result<_, _>>()
+        .expect("source should be lexed");
+    Parser::new(tokens.iter()).parse().expect("source should be parsed")
+}
@@ -0,0 +1,156 @@
+use std::io;
+
+use monkeylang::lexer::{Lexer, Token};
+use monkeylang::parser::*;
+use monkeylang::typecheck::{self, check};
+
+#[test]
+fn check_matching_annotations() {
+    let tests = vec![
+        "let x: int = 5;",
+        "let xs: [string] = [\"a\", \"b\"];",
+        "let h: {string: bool} = {\"a\": true};",
+        "let f = fn(a: int, b: int) -> bool { a < b }; let y: bool = f(1, 2);",
+        "let x: int = 1; x = x * 2;",
+        "let x: int = 1; x += 2; let s: string = \"a\"; s += \"b\";",
+        "let f = fn(a: int) -> int { if (a > 0) { return a; } return -a; };",
+    ];
+    for test in tests {
+        assert_eq!(check(&parse(test)), vec![], "{}", test);
+    }
+}
+
+#[test]
+fn check_unannotated_code() {
+    let tests = vec![
+        "let f = fn(a) { a + 1 }; f(\"a\", 2);",
+        "let x: any = true;",
+        "let xs: [int] = [];",
+        "let y: int = unknown(1);",
+        "let x = 5; x = \"s\";",
+        "let x = \"a\"; x = 5; let y: int = x;",
+        "let xs = [1, 2]; xs[0] = true; let s: string = xs[1];",
+    ];
+    for test in tests {
+        assert_eq!(check(&parse(test)), vec![], "{}", test);
+    }
+}
+
+#[test]
+fn check_mismatched_annotations() {
+    let tests = vec![
+        (
+            "let x: int = true;",
+            vec![typecheck::Error::Mismatch {
+                expected: int(),
+                found: Type::Named("bool".to_string()),
+                expression: Expression::Boolean(true),
+            }],
+        ),
+        (
+            "let f = fn(a: int) -> string { a }; f(\"a\");",
+            vec![
+                typecheck::Error::Mismatch {
+                    expected: Type::Named("string".to_string()),
+                    found: int(),
+                    expression: Expression::Identifier("a".to_string()),
+                },
+                typecheck::Error::Mismatch {
+                    expected: int(),
+                    found: Type::Named("string".to_string()),
+                    expression: Expression::String("a".to_string()),
+                },
+            ],
+        ),
+        (
+            "let x: int = 1; x = \"one\";",
+            vec![typecheck::Error::Mismatch {
+                expected: int(),
+                found: Type::Named("string".to_string()),
+                expression: Expression::String("one".to_string()),
+            }],
+        ),
+        (
+            "let x: string = \"a\"; x = 5; let y: int = x;",
+            vec![
+                typecheck::Error::Mismatch {
+                    expected: Type::Named("string".to_string()),
+                    found: int(),
+                    expression: Expression::Integer(5),
+                },
+                typecheck::Error::Mismatch {
+                    expected: int(),
+                    found: Type::Named("string".to_string()),
+                    expression: Expression::Identifier("x".to_string()),
+                },
+            ],
+        ),
+        (
+            "let xs: [int] = [1, 2]; xs[0] = true;",
+            vec![typecheck::Error::Mismatch {
+                expected: int(),
+                found: Type::Named("bool".to_string()),
+                expression: Expression::Boolean(true),
+            }],
+        ),
+        (
+            "let f = fn(a: int) -> int { a }; let y = unknown(); y + f(\"s\");",
+            vec![typecheck::Error::Mismatch {
+                expected: int(),
+                found: Type::Named("string".to_string()),
+                expression: Expression::String("s".to_string()),
+            }],
+        ),
+        (
+            "let xs: [int] = [1, 2]; for (x in xs) { let s: string = x; }",
+            vec![typecheck::Error::Mismatch {
+                expected: Type::Named("string".to_string()),
+                found: int(),
+                expression: Expression::Identifier("x".to_string()),
+            }],
+        ),
+        (
+            "fn count(n: int) -> int { if (n > 0) { return count(n - 1); } count(\"zero\") }",
+            vec![typecheck::Error::Mismatch {
+                expected: int(),
+                found: Type::Named("string".to_string()),
+                expression: Expression::String("zero".to_string()),
+            }],
+        ),
+        (
+            "let f = fn(a: int = \"a\") -> int { a }; let s: string = f();",
+            vec![typecheck::Error::Mismatch {
+                expected: int(),
+                found: Type::Named("string".to_string()),
+                expression: Expression::String("a".to_string()),
+            }],
+        ),
+    ];
+    for test in tests {
+        assert_eq!(check(&parse(test.0)), test.1, "{}", test.0);
+    }
+}
+
+#[test]
+fn check_argument_count() {
+    let errors = check(&parse("let f = fn(a: int) -> int { a }; f(1, 2);"));
+    assert!(matches!(
+        errors.as_slice(),
+        [typecheck::Error::ArgumentCount {
+            expected: 1,
+            got: 2,
+            expression: _
+        }]
+    ));
+}
+
+fn int() -> Type {
+    Type::Named("int".to_string())
+}
+
+fn parse(src: &str) -> AST {
+    let tokens: Vec<Token> = Lexer::new(io::Cursor::new(src))
+        .collect::<Result<_, _>>()
+        .expect("source should be lexed");
+    Parser::new(tokens.iter()).parse().expect("source should be parsed")
+}
@@ -0,0 +1,118 @@
+use std::convert::Infallible;
+use std::io;
+
+use monkeylang::lexer::{Lexer, Token};
+use monkeylang::parser::visit::{self, Folder, MutVisitor, Visitor};
+use monkeylang::parser::*;
+
+#[derive(Default)]
+struct Identifiers(Vec<String>);
+
+impl Visitor<'_> for Identifiers {
+    fn visit_expression(&mut self, exp: &Expression) {
+        if let Expression::Identifier(name) = exp {
+            self.0.push(name.clone());
+        }
+        visit::walk_expression(self, exp)
+    }
+}
+
+#[test]
+fn visit_every_expression() {
+    let ast = parse(
+        "
+let f = fn(x) { if (a) { b } else { c } };
+while (d) { e[g] = h(i); }
+match (j) { [k] if l => m, _ => n };
+",
+    );
+    let mut idents = Identifiers::default();
+    idents.visit_block(ast.statements());
+    let expected = ["a", "b", "c", "d", "e", "g", "h", "i", "j", "l", "m", "n"];
+    assert_eq!(idents.0, expected);
+}
+
+#[derive(Default)]
+struct Blocks(usize);
+
+impl Visitor<'_> for Blocks {
+    fn visit_block(&mut self, block: &[Statement]) {
+        self.0 += 1;
+        visit::walk_block(self, block)
+    }
+}
+
+#[test]
+fn visit_if_branches_as_blocks() {
+    let ast = parse("if (x) { 1 } else { if (y) { 2 } }");
+    let mut blocks = Blocks::default();
+    for st in ast.statements() {
+        blocks.visit_statement(st);
+    }
+    assert_eq!(blocks.0, 3);
+}
+
+struct Rename;
+
+impl MutVisitor for Rename {
+    fn visit_expression_mut(&mut self, exp: &mut Expression) {
+        if let Expression::Identifier(name) = exp {
+            if name == "x" {
+                *name = "y".to_string();
+            }
+        }
+        visit::walk_expression_mut(self, exp)
+    }
+
+    fn visit_pattern_mut(&mut self, pattern: &mut Pattern) {
+        if let Pattern::Identifier(name) = pattern {
+            if name == "x" {
+                *name = "y".to_string();
+            }
+        }
+        visit::walk_pattern_mut(self, pattern)
+    }
+}
+
+#[test]
+fn rename_in_place() {
+    let mut ast = parse("let [x, z] = a; if (x) { x + 1 } else { [x] }");
+    Rename.visit_block_mut(ast.statements_mut());
+    assert_eq!(ast, parse("let [y, z] = a; if (y) { y + 1 } else { [y] }"));
+}
+
+struct ConstantFolder;
+
+impl Folder for ConstantFolder {
+    type Error = Infallible;
+
+    fn fold_expression(&mut self, exp: Expression) -> Result<Expression, Infallible> {
+        let exp = visit::walk_expression_fold(self, exp)?;
+        Ok(match exp {
+            Expression::Binary { left, operator, right } => match (*left, operator, *right) {
+                (Expression::Integer(l), Token::Plus, Expression::Integer(r)) => Expression::Integer(l + r),
+                (Expression::Integer(l), Token::Asterisk, Expression::Integer(r)) => Expression::Integer(l * r),
+                (left, operator, right) => Expression::Binary {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                },
+            },
+            exp => exp,
+        })
+    }
+}
+
+#[test]
+fn fold_constants() {
+    let ast = parse("let x = 1 + 2 * 3; fn() { return x + 4 * 5; };");
+    let folded = ConstantFolder.fold_block(ast.statements().to_vec()).unwrap();
+    assert_eq!(folded, parse("let x = 7; fn() { return x + 20; };").statements());
+}
+
+fn parse(src: &str) -> AST {
+    let tokens: Vec<Token> = Lexer::new(io::Cursor::new(src))
+        .collect::<Result<_, _>>()
+        .expect("source should be lexed");
+    Parser::new(tokens.iter()).parse().expect("source should be parsed")
+}